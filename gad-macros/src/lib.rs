@@ -0,0 +1,322 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Procedural macros for the crate `gad`.
+//!
+//! The macros are re-exported by `gad` when the feature `macros` is enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, BinOp, DeriveInput, Expr, Ident, Token, UnOp,
+};
+
+mod net;
+
+/// Operations of `gad` that do not return a `Result`. Other functions are assumed to be
+/// fallible, unless they are listed by the argument `infallible = [..]` of `gad_expr!`.
+const INFALLIBLE_OPERATIONS: &[&str] = &[
+    "abs",
+    "addc",
+    "constant_as",
+    "cos",
+    "detach",
+    "exp",
+    "flat",
+    "log",
+    "log1p",
+    "mulc",
+    "neg",
+    "norm2",
+    "ones",
+    "powc",
+    "reciprocal",
+    "relu",
+    "scale",
+    "setc",
+    "sigmoid",
+    "sign",
+    "sin",
+    "sqrt",
+    "tanh",
+    "zeros",
+];
+
+/// Rewrite a nested formula into a sequence of calls to the operations of an algebra.
+///
+/// The first argument is the algebra (typically a variable `g`), the second one is the formula.
+/// * Operators `+`, `-`, `*`, `/` between values are mapped to `add`, `sub`, `mul`, `div`.
+/// * Operators involving a literal constant are mapped to `addc` and `mulc`, except for
+/// divisions, which divide by a value filled with the constant (see `setc`). Constants of
+/// type `f32` should be written with an explicit suffix (e.g. `2.0f32`).
+/// * A function call `f(x1, .. xN)` is mapped to the operation `g.f(&x1, .. &xN)`, except
+/// that literal arguments are passed by value (e.g. `powc(x, 3)` or `transpose(x, false)`).
+/// * Any other expression (e.g. a variable `x` or `v[0]`) is a value of the algebra.
+/// * Fallible operations are followed by `?`, hence the macro must be called in a function
+/// returning a compatible `Result` type. Calls to functions that are not operations of `gad`
+/// are assumed to be fallible, unless they are listed by the optional argument
+/// `infallible = [f1, .. fN]`.
+///
+/// For instance, `gad_expr!(g, x * sin(y) + 1.0)` expands to
+/// ```ignore
+/// {
+///     let t0 = g.sin(&y);
+///     let t1 = g.mul(&x, &t0)?;
+///     let t2 = g.addc(&t1, 1.0);
+///     t2
+/// }
+/// ```
+#[proc_macro]
+pub fn gad_expr(input: TokenStream) -> TokenStream {
+    let ExprInput {
+        algebra,
+        expr,
+        infallible,
+    } = parse_macro_input!(input as ExprInput);
+    let mut builder = Builder::new(algebra, infallible);
+    match builder.translate(&expr) {
+        Ok(operand) => builder.finish(operand).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Implement the trait `Net` for a struct whose fields are sub-networks (e.g. `WeightData`
+/// or other structs deriving `Net`).
+///
+/// The attribute `#[net(algebra = A, input = I, output = O)]` is required, where `A` is
+/// the algebra of the implementation (typically a type parameter of the struct). The
+/// computation itself is defined by a method `forward` (or any function given by the option
+/// `forward = path`) with the following signature:
+/// ```ignore
+/// fn forward(&self, graph: &mut A, input: I, info: &mut <Self as Net<A>>::GradientInfo) -> Result<O>;
+/// ```
+/// Each sub-network must be evaluated exactly once using `Net::eval_into` and the
+/// corresponding field of `info`. Fields that are not networks must be marked with
+/// `#[net(skip)]`.
+///
+/// The macro also defines a struct `{Name}Weights` with one field for each sub-network. This
+/// struct implements `WeightOps` and `CastWeights`, and represents both the weights and the
+/// gradient information of the network.
+///
+/// For instance:
+/// ```ignore
+/// #[derive(Net)]
+/// #[net(algebra = A, input = Value<f32>, output = Value<f32>)]
+/// struct Affine<A> {
+///     a: WeightData<f32, A>,
+///     b: WeightData<f32, A>,
+/// }
+///
+/// impl<A> Affine<A> where A: ... {
+///     fn forward(&self, g: &mut A, x: Value<f32>, info: &mut <Self as Net<A>>::GradientInfo) -> Result<Value<f32>> {
+///         let a = self.a.eval_into(g, (), &mut info.a)?;
+///         let b = self.b.eval_into(g, (), &mut info.b)?;
+///         Ok(gad_expr!(g, a * x + b))
+///     }
+/// }
+/// ```
+#[proc_macro_derive(Net, attributes(net))]
+pub fn derive_net(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match net::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Arguments of `gad_expr!`.
+struct ExprInput {
+    algebra: Expr,
+    expr: Expr,
+    infallible: Vec<Ident>,
+}
+
+impl Parse for ExprInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let algebra = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let expr = input.parse()?;
+        let mut infallible = Vec::new();
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            if !input.is_empty() {
+                let name = input.parse::<Ident>()?;
+                if name != "infallible" {
+                    return Err(syn::Error::new_spanned(name, "expected `infallible`"));
+                }
+                input.parse::<Token![=]>()?;
+                let content;
+                syn::bracketed!(content in input);
+                infallible = content
+                    .parse_terminated::<_, Token![,]>(Ident::parse)?
+                    .into_iter()
+                    .collect();
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+            }
+        }
+        Ok(Self {
+            algebra,
+            expr,
+            infallible,
+        })
+    }
+}
+
+/// Result of the translation of a sub-expression.
+enum Operand {
+    /// A value computed by the macro and owned by a local variable.
+    Temp(Ident),
+    /// An expression evaluating to a reference to a value.
+    Ref(TokenStream2),
+    /// A constant expression.
+    Const(TokenStream2),
+}
+
+/// Accumulate the `let` statements of the expansion.
+struct Builder {
+    algebra: Expr,
+    /// User-defined functions declared by the caller as not returning a `Result`.
+    infallible: Vec<Ident>,
+    statements: Vec<TokenStream2>,
+}
+
+impl Operand {
+    /// Tokens for a function argument.
+    fn argument(&self) -> TokenStream2 {
+        match self {
+            Operand::Temp(ident) => quote!(&#ident),
+            Operand::Ref(tokens) => tokens.clone(),
+            Operand::Const(tokens) => tokens.clone(),
+        }
+    }
+}
+
+impl Builder {
+    fn new(algebra: Expr, infallible: Vec<Ident>) -> Self {
+        Self {
+            algebra,
+            infallible,
+            statements: Vec::new(),
+        }
+    }
+
+    fn fresh_ident(&self) -> Ident {
+        format_ident!("__gad_{}", self.statements.len(), span = Span::mixed_site())
+    }
+
+    fn call(&mut self, op: &Ident, args: &[&Operand], fallible: bool) -> Operand {
+        let algebra = &self.algebra;
+        let args = args.iter().map(|arg| arg.argument());
+        let question = if fallible { quote!(?) } else { quote!() };
+        let ident = self.fresh_ident();
+        self.statements
+            .push(quote! { let #ident = #algebra.#op(#(#args),*)#question; });
+        Operand::Temp(ident)
+    }
+
+    /// Call an operation of the traits of `gad` used to translate operators.
+    fn call_named(&mut self, op: &str, args: &[&Operand], fallible: bool) -> Operand {
+        self.call(&Ident::new(op, Span::call_site()), args, fallible)
+    }
+
+    fn translate(&mut self, expr: &Expr) -> syn::Result<Operand> {
+        match expr {
+            Expr::Paren(e) => self.translate(&e.expr),
+            Expr::Group(e) => self.translate(&e.expr),
+            Expr::Lit(_) => Ok(Operand::Const(quote!(#expr))),
+            Expr::Unary(e) if matches!(e.op, UnOp::Neg(_)) => match self.translate(&e.expr)? {
+                Operand::Const(c) => Ok(Operand::Const(quote!(-(#c)))),
+                v => Ok(self.call_named("neg", &[&v], false)),
+            },
+            Expr::Binary(e) => {
+                let left = self.translate(&e.left)?;
+                let right = self.translate(&e.right)?;
+                self.translate_binary(expr, &e.op, left, right)
+            }
+            Expr::Call(e) => {
+                let op = match &*e.func {
+                    Expr::Path(p) if p.qself.is_none() => p.path.get_ident().cloned(),
+                    _ => None,
+                }
+                .ok_or_else(|| syn::Error::new_spanned(&e.func, "expected an operation name"))?;
+                let args = e
+                    .args
+                    .iter()
+                    .map(|arg| self.translate(arg))
+                    .collect::<syn::Result<Vec<_>>>()?;
+                let fallible = !INFALLIBLE_OPERATIONS.contains(&op.to_string().as_str())
+                    && !self.infallible.contains(&op);
+                Ok(self.call(&op, &args.iter().collect::<Vec<_>>(), fallible))
+            }
+            _ => {
+                let ident = self.fresh_ident();
+                self.statements.push(quote! { let #ident = &(#expr); });
+                Ok(Operand::Ref(quote!(#ident)))
+            }
+        }
+    }
+
+    fn translate_binary(
+        &mut self,
+        expr: &Expr,
+        op: &BinOp,
+        left: Operand,
+        right: Operand,
+    ) -> syn::Result<Operand> {
+        use Operand::Const;
+
+        let result = match (op, &left, &right) {
+            (BinOp::Add(_), Const(c1), Const(c2)) => Const(quote!((#c1) + (#c2))),
+            (BinOp::Sub(_), Const(c1), Const(c2)) => Const(quote!((#c1) - (#c2))),
+            (BinOp::Mul(_), Const(c1), Const(c2)) => Const(quote!((#c1) * (#c2))),
+            (BinOp::Div(_), Const(c1), Const(c2)) => Const(quote!((#c1) / (#c2))),
+            (BinOp::Add(_), Const(c), v) | (BinOp::Add(_), v, Const(c)) => {
+                self.call_named("addc", &[v, &Const(c.clone())], false)
+            }
+            (BinOp::Sub(_), v, Const(c)) => {
+                self.call_named("addc", &[v, &Const(quote!(-(#c)))], false)
+            }
+            (BinOp::Sub(_), Const(c), v) => {
+                let n = self.call_named("neg", &[v], false);
+                self.call_named("addc", &[&n, &Const(c.clone())], false)
+            }
+            (BinOp::Mul(_), Const(c), v) | (BinOp::Mul(_), v, Const(c)) => {
+                self.call_named("mulc", &[v, &Const(c.clone())], false)
+            }
+            (BinOp::Div(_), v, Const(c)) => {
+                let d = self.call_named("setc", &[v, &Const(c.clone())], false);
+                self.call_named("div", &[v, &d], true)
+            }
+            (BinOp::Div(_), Const(c), v) => {
+                let n = self.call_named("setc", &[v, &Const(c.clone())], false);
+                self.call_named("div", &[&n, v], true)
+            }
+            (BinOp::Add(_), v1, v2) => self.call_named("add", &[v1, v2], true),
+            (BinOp::Sub(_), v1, v2) => self.call_named("sub", &[v1, v2], true),
+            (BinOp::Mul(_), v1, v2) => self.call_named("mul", &[v1, v2], true),
+            (BinOp::Div(_), v1, v2) => self.call_named("div", &[v1, v2], true),
+            _ => return Err(syn::Error::new_spanned(expr, "unsupported binary operator")),
+        };
+        Ok(result)
+    }
+
+    fn finish(self, operand: Operand) -> TokenStream2 {
+        let algebra = &self.algebra;
+        let statements = &self.statements;
+        let result = match operand {
+            Operand::Temp(ident) => quote!(#ident),
+            Operand::Ref(tokens) => quote!(::std::clone::Clone::clone(#tokens)),
+            Operand::Const(c) => quote!(#algebra.constant(#c)),
+        };
+        quote! {
+            {
+                #(#statements)*
+                #result
+            }
+        }
+    }
+}
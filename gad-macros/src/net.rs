@@ -0,0 +1,250 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    Attribute, Data, DeriveInput, Fields, Ident, Path, Token, Type,
+};
+
+/// Arguments of the attribute `#[net(..)]` on the derived struct.
+#[derive(Default)]
+struct NetOptions {
+    algebra: Option<Type>,
+    input: Option<Type>,
+    output: Option<Type>,
+    forward: Option<Path>,
+}
+
+/// A single argument `key = value` of the attribute `#[net(..)]`.
+enum NetOption {
+    Algebra(Type),
+    Input(Type),
+    Output(Type),
+    Forward(Path),
+}
+
+impl Parse for NetOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        match key.to_string().as_str() {
+            "algebra" => Ok(NetOption::Algebra(input.parse()?)),
+            "input" => Ok(NetOption::Input(input.parse()?)),
+            "output" => Ok(NetOption::Output(input.parse()?)),
+            "forward" => Ok(NetOption::Forward(input.parse()?)),
+            _ => Err(syn::Error::new_spanned(
+                key,
+                "expected `algebra`, `input`, `output`, or `forward`",
+            )),
+        }
+    }
+}
+
+fn is_net_attribute(attr: &Attribute) -> bool {
+    attr.path.is_ident("net")
+}
+
+impl NetOptions {
+    fn from_attributes(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|attr| is_net_attribute(attr)) {
+            let args =
+                attr.parse_args_with(Punctuated::<NetOption, Token![,]>::parse_terminated)?;
+            for arg in args {
+                match arg {
+                    NetOption::Algebra(t) => options.algebra = Some(t),
+                    NetOption::Input(t) => options.input = Some(t),
+                    NetOption::Output(t) => options.output = Some(t),
+                    NetOption::Forward(p) => options.forward = Some(p),
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Whether a field is marked with `#[net(skip)]`.
+fn is_skipped(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut skipped = false;
+    for attr in attrs.iter().filter(|attr| is_net_attribute(attr)) {
+        let ident: Ident = attr.parse_args()?;
+        if ident != "skip" {
+            return Err(syn::Error::new_spanned(ident, "expected `skip`"));
+        }
+        skipped = true;
+    }
+    Ok(skipped)
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let options = NetOptions::from_attributes(&input.attrs)?;
+    let missing =
+        |key| syn::Error::new_spanned(name, format!("missing attribute `#[net({} = ..)]`", key));
+    let algebra = options.algebra.ok_or_else(|| missing("algebra"))?;
+    let input_type = options.input.ok_or_else(|| missing("input"))?;
+    let output_type = options.output.ok_or_else(|| missing("output"))?;
+    let forward = options
+        .forward
+        .unwrap_or_else(|| parse_quote!(Self::forward));
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "`#[derive(Net)]` requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "`#[derive(Net)]` only applies to structs",
+            ))
+        }
+    };
+    let mut nets = Vec::new();
+    for field in fields {
+        if !is_skipped(&field.attrs)? {
+            nets.push(field);
+        }
+    }
+    let idents = nets
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named field"))
+        .collect::<Vec<_>>();
+    let types = nets.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let visibilities = nets.iter().map(|field| &field.vis);
+    let params = (0..nets.len())
+        .map(|i| format_ident!("W{}", i))
+        .collect::<Vec<_>>();
+    let masters = (0..nets.len())
+        .map(|i| format_ident!("M{}", i))
+        .collect::<Vec<_>>();
+
+    let weights = format_ident!("{}Weights", name);
+    let weights_doc = format!(
+        "Weights (and gradient information) of the network [`{}`], with one field for each \
+         sub-network.",
+        name
+    );
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    where_clause
+        .predicates
+        .push(parse_quote!(#algebra: ::gad::net::HasGradientReader));
+    for ty in &types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::gad::net::Net<#algebra>));
+    }
+
+    Ok(quote! {
+        #[doc = #weights_doc]
+        #[derive(Debug, Clone, ::gad::serde::Serialize, ::gad::serde::Deserialize)]
+        #[serde(crate = "::gad::serde")]
+        #vis struct #weights<#(#params),*> {
+            #(#visibilities #idents: #params,)*
+        }
+
+        impl #impl_generics ::gad::net::Net<#algebra> for #name #ty_generics #where_clause {
+            type Input = #input_type;
+            type Output = #output_type;
+            type Weights = #weights<#(<#types as ::gad::net::Net<#algebra>>::Weights),*>;
+            type GradientInfo = #weights<#(
+                ::std::option::Option<<#types as ::gad::net::Net<#algebra>>::GradientInfo>
+            ),*>;
+
+            fn eval_with_gradient_info(
+                &self,
+                graph: &mut #algebra,
+                input: Self::Input,
+            ) -> ::gad::error::Result<(Self::Output, Self::GradientInfo)> {
+                let mut info = #weights { #(#idents: ::std::option::Option::None,)* };
+                let output = #forward(self, graph, input, &mut info)?;
+                ::std::result::Result::Ok((output, info))
+            }
+
+            fn get_weights(&self) -> Self::Weights {
+                #weights {
+                    #(#idents: <#types as ::gad::net::Net<#algebra>>::get_weights(&self.#idents),)*
+                }
+            }
+
+            fn set_weights(&mut self, weights: Self::Weights) -> ::gad::error::Result<()> {
+                #(<#types as ::gad::net::Net<#algebra>>::set_weights(
+                    &mut self.#idents,
+                    weights.#idents,
+                )?;)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn update_weights(&mut self, delta: Self::Weights) -> ::gad::error::Result<()> {
+                #(<#types as ::gad::net::Net<#algebra>>::update_weights(
+                    &mut self.#idents,
+                    delta.#idents,
+                )?;)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn read_weight_gradients(
+                &self,
+                info: Self::GradientInfo,
+                reader: &<#algebra as ::gad::net::HasGradientReader>::GradientReader,
+            ) -> ::gad::error::Result<Self::Weights> {
+                ::std::result::Result::Ok(#weights {
+                    #(#idents: <#types as ::gad::net::Net<#algebra>>::read_weight_gradients(
+                        &self.#idents,
+                        info.#idents.ok_or_else(|| {
+                            ::gad::error::Error::missing_gradient(::gad::func_name!())
+                        })?,
+                        reader,
+                    )?,)*
+                })
+            }
+        }
+
+        impl<T, #(#params),*> ::gad::net::WeightOps<T> for #weights<#(#params),*>
+        where
+            T: Copy,
+            #(#params: ::gad::net::WeightOps<T>,)*
+        {
+            fn add_assign(&mut self, _other: Self) -> ::gad::error::Result<()> {
+                #(::gad::net::WeightOps::<T>::add_assign(&mut self.#idents, _other.#idents)?;)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn scale(&self, _rhs: T) -> Self {
+                #weights {
+                    #(#idents: ::gad::net::WeightOps::<T>::scale(&self.#idents, _rhs),)*
+                }
+            }
+        }
+
+        impl<#(#masters,)* #(#params),*> ::gad::net::CastWeights<#weights<#(#masters),*>>
+            for #weights<#(#params),*>
+        where
+            #(#params: ::gad::net::CastWeights<#masters>,)*
+        {
+            fn cast_from(_weights: &#weights<#(#masters),*>) -> Self {
+                #weights {
+                    #(#idents: ::gad::net::CastWeights::<#masters>::cast_from(&_weights.#idents),)*
+                }
+            }
+
+            fn cast_into(&self) -> #weights<#(#masters),*> {
+                #weights {
+                    #(#idents: ::gad::net::CastWeights::<#masters>::cast_into(&self.#idents),)*
+                }
+            }
+        }
+    })
+}
@@ -8,7 +8,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
     Check, Eval, Number,
 };
 
@@ -285,8 +285,8 @@ impl AnalyticAlgebra<()> for Check {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims> AnalyticAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> AnalyticAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
@@ -295,12 +295,13 @@ macro_rules! impl_graph {
                 + ArithAlgebra<D>
                 + ConstArithAlgebra<D, i16>
                 + LinkedAlgebra<Value<D>, D>,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
             fn exp(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().exp(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("exp", |eval| eval.exp(v.data()));
+                self.make_named_node("exp", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -315,8 +316,8 @@ macro_rules! impl_graph {
             }
 
             fn log(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().log(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("log", |eval| eval.log(v.data()));
+                self.make_named_node("log", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -330,8 +331,8 @@ macro_rules! impl_graph {
             }
 
             fn log1p(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().log1p(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("log1p", |eval| eval.log1p(v.data()));
+                self.make_named_node("log1p", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -346,8 +347,8 @@ macro_rules! impl_graph {
             }
 
             fn sin(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().sin(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("sin", |eval| eval.sin(v.data()));
+                self.make_named_node("sin", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -362,8 +363,8 @@ macro_rules! impl_graph {
             }
 
             fn cos(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().cos(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("cos", |eval| eval.cos(v.data()));
+                self.make_named_node("cos", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -379,8 +380,8 @@ macro_rules! impl_graph {
             }
 
             fn tanh(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().tanh(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("tanh", |eval| eval.tanh(v.data()));
+                self.make_named_node("tanh", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -398,8 +399,8 @@ macro_rules! impl_graph {
             }
 
             fn sigmoid(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().sigmoid(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("sigmoid", |eval| eval.sigmoid(v.data()));
+                self.make_named_node("sigmoid", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -417,8 +418,8 @@ macro_rules! impl_graph {
             }
 
             fn reciprocal(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().reciprocal(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("reciprocal", |eval| eval.reciprocal(v.data()));
+                self.make_named_node("reciprocal", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -435,8 +436,8 @@ macro_rules! impl_graph {
             }
 
             fn sqrt(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().sqrt(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("sqrt", |eval| eval.sqrt(v.data()));
+                self.make_named_node("sqrt", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -453,32 +454,38 @@ macro_rules! impl_graph {
             }
 
             fn div(&mut self, v0: &Value<D>, v1: &Value<D>) -> Result<Value<D>> {
-                let result = self.eval().div(v0.data(), v1.data())?;
-                let value = self.make_node(result, vec![v0.input(), v1.input()], {
-                    let v0 = v0.clone();
-                    let v1 = v1.clone();
-                    move |graph, store, gradient| {
-                        let c1 = graph.link(&v1);
-                        let r1 = graph.reciprocal(&c1);
-                        let g0 = graph.mul(&gradient, &r1)?;
-                        if let Some(id) = v0.id() {
-                            store.add_gradient(graph, id, &g0)?;
-                        }
-                        if let Some(id) = v1.id() {
-                            let c0 = graph.link(&v0);
-                            let c = graph.mul(&g0, &r1)?;
-                            let c = graph.mul(&c, &c0)?;
-                            let g1 = graph.neg(&c);
-                            store.add_gradient(graph, id, &g1)?;
+                let result = self.eval_timed("div", |eval| eval.div(v0.data(), v1.data()))?;
+                let value = self.make_named_node(
+                    "div",
+                    result,
+                    vec![v0.input(), v1.input()],
+                    v0.size_in_bytes() + v1.size_in_bytes(),
+                    {
+                        let v0 = v0.clone();
+                        let v1 = v1.clone();
+                        move |graph, store, gradient| {
+                            let c1 = graph.link(&v1);
+                            let r1 = graph.reciprocal(&c1);
+                            let g0 = graph.mul(&gradient, &r1)?;
+                            if let Some(id) = v0.id() {
+                                store.add_gradient(graph, id, &g0)?;
+                            }
+                            if let Some(id) = v1.id() {
+                                let c0 = graph.link(&v0);
+                                let c = graph.mul(&g0, &r1)?;
+                                let c = graph.mul(&c, &c0)?;
+                                let g1 = graph.neg(&c);
+                                store.add_gradient(graph, id, &g1)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
         }
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
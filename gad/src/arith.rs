@@ -6,7 +6,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
     Check, Eval, Number,
 };
 
@@ -151,30 +151,31 @@ impl ArithAlgebra<()> for Check {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims> ArithAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> ArithAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
                 + CoreAlgebra<D, Value = D>
                 + ArithAlgebra<D>
                 + LinkedAlgebra<Value<D>, D>,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
             fn zeros(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().zeros(v.data());
+                let result = self.eval_timed("zeros", |eval| eval.zeros(v.data()));
                 self.constant(result)
             }
 
             fn ones(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().ones(v.data());
+                let result = self.eval_timed("ones", |eval| eval.ones(v.data()));
                 self.constant(result)
             }
 
             fn neg(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().neg(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("neg", |eval| eval.neg(v.data()));
+                self.make_named_node("neg", result, vec![v.input()], 0, {
                     let id = v.id();
                     move |graph, store, gradient| {
                         if let Some(id) = id {
@@ -187,8 +188,8 @@ macro_rules! impl_graph {
             }
 
             fn sub(&mut self, v0: &Value<D>, v1: &Value<D>) -> Result<Value<D>> {
-                let result = self.eval().sub(v0.data(), v1.data())?;
-                let value = self.make_node(result, vec![v0.input(), v1.input()], {
+                let result = self.eval_timed("sub", |eval| eval.sub(v0.data(), v1.data()))?;
+                let value = self.make_named_node("sub", result, vec![v0.input(), v1.input()], 0, {
                     let id0 = v0.id();
                     let id1 = v1.id();
                     move |graph, store, gradient| {
@@ -206,29 +207,35 @@ macro_rules! impl_graph {
             }
 
             fn mul(&mut self, v0: &Value<D>, v1: &Value<D>) -> Result<Value<D>> {
-                let result = self.eval().mul(v0.data(), v1.data())?;
-                let value = self.make_node(result, vec![v0.input(), v1.input()], {
-                    let v0 = v0.clone();
-                    let v1 = v1.clone();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v0.id() {
-                            let c1 = graph.link(&v1);
-                            let grad = graph.mul(&gradient, c1)?;
-                            store.add_gradient(graph, id, &grad)?;
-                        }
-                        if let Some(id) = v1.id() {
-                            let c0 = graph.link(&v0);
-                            let grad = graph.mul(c0, &gradient)?;
-                            store.add_gradient(graph, id, &grad)?;
+                let result = self.eval_timed("mul", |eval| eval.mul(v0.data(), v1.data()))?;
+                let value = self.make_named_node(
+                    "mul",
+                    result,
+                    vec![v0.input(), v1.input()],
+                    v0.size_in_bytes() + v1.size_in_bytes(),
+                    {
+                        let v0 = v0.clone();
+                        let v1 = v1.clone();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v0.id() {
+                                let c1 = graph.link(&v1);
+                                let grad = graph.mul(&gradient, c1)?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            if let Some(id) = v1.id() {
+                                let c0 = graph.link(&v0);
+                                let grad = graph.mul(c0, &gradient)?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
         }
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
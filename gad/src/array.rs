@@ -6,7 +6,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
 };
 
 /// Array operations.
@@ -194,8 +194,8 @@ mod af_arith {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, T, Dims> ArrayAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, T, Dims> ArrayAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
@@ -205,6 +205,7 @@ macro_rules! impl_graph {
                 + LinkedAlgebra<Value<T>, T>
                 + ArrayAlgebra<D, Scalar = T, Dims = Dims>,
             Dims: PartialEq + Clone + Copy + std::fmt::Debug + Default + 'static + Send + Sync,
+            S: $store<D> + $store<T>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             T: crate::Number,
         {
@@ -212,8 +213,8 @@ macro_rules! impl_graph {
             type Scalar = Value<T>;
 
             fn flat(&mut self, v: &Value<D>) -> Value<D> {
-                let result = self.eval().flat(v.data());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("flat", |eval| eval.flat(v.data()));
+                self.make_named_node("flat", result, vec![v.input()], 0, {
                     let vdims = v.data().dims();
                     let id = v.id();
                     move |graph, store, gradient| {
@@ -227,8 +228,8 @@ macro_rules! impl_graph {
             }
 
             fn moddims(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().moddims(v.data(), rdims)?;
-                let value = self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("moddims", |eval| eval.moddims(v.data(), rdims))?;
+                let value = self.make_named_node("moddims", result, vec![v.input()], 0, {
                     let vdims = v.data().dims();
                     let id = v.id();
                     move |graph, store, gradient| {
@@ -243,8 +244,8 @@ macro_rules! impl_graph {
             }
 
             fn tile_as(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().tile_as(v.data(), rdims)?;
-                let value = self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("tile_as", |eval| eval.tile_as(v.data(), rdims))?;
+                let value = self.make_named_node("tile_as", result, vec![v.input()], 0, {
                     let vdims = v.data().dims();
                     let id = v.id();
                     move |graph, store, gradient| {
@@ -259,8 +260,8 @@ macro_rules! impl_graph {
             }
 
             fn sum_as(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().sum_as(v.data(), rdims)?;
-                let value = self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("sum_as", |eval| eval.sum_as(v.data(), rdims))?;
+                let value = self.make_named_node("sum_as", result, vec![v.input()], 0, {
                     let vdims = v.data().dims();
                     let id = v.id();
                     move |graph, store, gradient| {
@@ -275,83 +276,108 @@ macro_rules! impl_graph {
             }
 
             fn constant_as(&mut self, v: &Value<T>, dims: Dims) -> Value<D> {
-                let result = self.eval().constant_as(v.data(), dims);
-                let value = self.make_generic_node::<T, D, _, _, _, _>(result, vec![v.input()], {
-                    let id = v.id();
-                    move |graph, store, gradient| {
-                        if let Some(id) = id {
-                            let x = graph.sum_as(&gradient, Dims::default())?;
-                            let y = graph.as_scalar(&x)?;
-                            store.add_gradient::<T, _>(graph, id, &y)?;
+                let result =
+                    self.eval_timed("constant_as", |eval| eval.constant_as(v.data(), dims));
+                let value = self.make_named_generic_node::<T, D, _, _, _, _>(
+                    "constant_as",
+                    result,
+                    vec![v.input()],
+                    0,
+                    {
+                        let id = v.id();
+                        move |graph, store, gradient| {
+                            if let Some(id) = id {
+                                let x = graph.sum_as(&gradient, Dims::default())?;
+                                let y = graph.as_scalar(&x)?;
+                                store.add_gradient::<T, _>(graph, id, &y)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 value
             }
 
             fn as_scalar(&mut self, v: &Value<D>) -> Result<Value<T>> {
-                let result = self.eval().as_scalar(v.data())?;
-                let value = self.make_generic_node::<D, T, _, _, _, _>(result, vec![v.input()], {
-                    let vdims = v.dims();
-                    let id = v.id();
-                    move |graph, store, gradient| {
-                        if let Some(id) = id {
-                            let x = graph.constant_as(&gradient, vdims);
-                            store.add_gradient::<D, _>(graph, id, &x)?;
+                let result = self.eval_timed("as_scalar", |eval| eval.as_scalar(v.data()))?;
+                let value = self.make_named_generic_node::<D, T, _, _, _, _>(
+                    "as_scalar",
+                    result,
+                    vec![v.input()],
+                    0,
+                    {
+                        let vdims = v.dims();
+                        let id = v.id();
+                        move |graph, store, gradient| {
+                            if let Some(id) = id {
+                                let x = graph.constant_as(&gradient, vdims);
+                                store.add_gradient::<D, _>(graph, id, &x)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
 
             fn scale(&mut self, v1: &Value<T>, v2: &Value<D>) -> Value<D> {
-                let result = self.eval().scale(v1.data(), v2.data());
-                let value = self.make_node(result, vec![v1.input(), v2.input()], {
-                    let v1 = v1.clone();
-                    let v2 = v2.clone();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v1.id() {
-                            let c2 = graph.link(&v2);
-                            let grad = graph.dot(&gradient, c2)?;
-                            store.add_gradient::<T, _>(graph, id, &grad)?;
-                        }
-                        if let Some(id) = v2.id() {
-                            let c1 = graph.link(&v1);
-                            let grad = graph.scale(c1, &gradient);
-                            store.add_gradient::<D, _>(graph, id, &grad)?;
+                let result = self.eval_timed("scale", |eval| eval.scale(v1.data(), v2.data()));
+                let value = self.make_named_node(
+                    "scale",
+                    result,
+                    vec![v1.input(), v2.input()],
+                    v1.size_in_bytes() + v2.size_in_bytes(),
+                    {
+                        let v1 = v1.clone();
+                        let v2 = v2.clone();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v1.id() {
+                                let c2 = graph.link(&v2);
+                                let grad = graph.dot(&gradient, c2)?;
+                                store.add_gradient::<T, _>(graph, id, &grad)?;
+                            }
+                            if let Some(id) = v2.id() {
+                                let c1 = graph.link(&v1);
+                                let grad = graph.scale(c1, &gradient);
+                                store.add_gradient::<D, _>(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 value
             }
 
             fn dot(&mut self, v1: &Value<D>, v2: &Value<D>) -> Result<Value<T>> {
-                let result = self.eval().dot(v1.data(), v2.data())?;
-                let value = self.make_node(result, vec![v1.input(), v2.input()], {
-                    let v1 = v1.clone();
-                    let v2 = v2.clone();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v1.id() {
-                            let c2 = graph.link(&v2);
-                            let grad = graph.scale(&gradient, c2);
-                            store.add_gradient::<D, _>(graph, id, &grad)?;
-                        }
-                        if let Some(id) = v2.id() {
-                            let c1 = graph.link(&v1);
-                            let grad = graph.scale(&gradient, c1);
-                            store.add_gradient::<D, _>(graph, id, &grad)?;
+                let result = self.eval_timed("dot", |eval| eval.dot(v1.data(), v2.data()))?;
+                let value = self.make_named_node(
+                    "dot",
+                    result,
+                    vec![v1.input(), v2.input()],
+                    v1.size_in_bytes() + v2.size_in_bytes(),
+                    {
+                        let v1 = v1.clone();
+                        let v2 = v2.clone();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v1.id() {
+                                let c2 = graph.link(&v2);
+                                let grad = graph.scale(&gradient, c2);
+                                store.add_gradient::<D, _>(graph, id, &grad)?;
+                            }
+                            if let Some(id) = v2.id() {
+                                let c1 = graph.link(&v1);
+                                let grad = graph.scale(&gradient, c1);
+                                store.add_gradient::<D, _>(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
         }
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
@@ -9,7 +9,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
 };
 
 /// Array-oriented comparison operations.
@@ -102,8 +102,8 @@ mod af_arith {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, T, Dims> ArrayCompareAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, T, Dims> ArrayCompareAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
@@ -117,58 +117,68 @@ macro_rules! impl_graph {
                 + LinkedAlgebra<Value<D>, D>
                 + LinkedAlgebra<Value<T>, T>,
             T: crate::Number,
+            S: $store<D> + $store<T>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Default + Copy + Clone + 'static + Send + Sync,
         {
             fn max_as(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().max_as(v.data(), rdims)?;
-                let value = self.make_node(result, vec![v.input()], {
-                    let v = v.clone();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v.id() {
-                            let v = graph.link(&v);
-                            let mask = graph.argmax_as(v, rdims)?;
-                            let tiled = graph.tile_as(&gradient, v.dims())?;
-                            let grad = graph.mul(&tiled, &mask)?;
-                            store.add_gradient::<D, _>(graph, id, &grad)?;
+                let result = self.eval_timed("max_as", |eval| eval.max_as(v.data(), rdims))?;
+                let value =
+                    self.make_named_node("max_as", result, vec![v.input()], v.size_in_bytes(), {
+                        let v = v.clone();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v.id() {
+                                let v = graph.link(&v);
+                                let mask = graph.argmax_as(v, rdims)?;
+                                let tiled = graph.tile_as(&gradient, v.dims())?;
+                                let grad = graph.mul(&tiled, &mask)?;
+                                store.add_gradient::<D, _>(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    });
                 Ok(value)
             }
 
             fn argmax_as(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().argmax_as(v.data(), rdims)?;
+                let result =
+                    self.eval_timed("argmax_as", |eval| eval.argmax_as(v.data(), rdims))?;
                 Ok(self.constant(result))
             }
 
             fn softmax_as(&mut self, v: &Value<D>, rdims: Dims) -> Result<Value<D>> {
-                let result = self.eval().softmax_as(v.data(), rdims)?;
-                let value = self.make_node(result, vec![v.input()], {
-                    let v = v.clone();
-                    let dims = v.dims();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v.id() {
-                            let v = graph.link(&v);
-                            let res = graph.softmax_as(v, rdims)?;
-                            let g1 = graph.mul(&gradient, &res)?;
-                            let g2 = {
-                                let rg = graph.sum_as(&g1, rdims)?;
-                                let g = graph.tile_as(&rg, dims)?;
-                                graph.mul(&g, &res)?
-                            };
-                            let grad = graph.sub(&g1, &g2)?;
-                            store.add_gradient::<D, _>(graph, id, &grad)?;
+                let result =
+                    self.eval_timed("softmax_as", |eval| eval.softmax_as(v.data(), rdims))?;
+                let value = self.make_named_node(
+                    "softmax_as",
+                    result,
+                    vec![v.input()],
+                    v.size_in_bytes(),
+                    {
+                        let v = v.clone();
+                        let dims = v.dims();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v.id() {
+                                let v = graph.link(&v);
+                                let res = graph.softmax_as(v, rdims)?;
+                                let g1 = graph.mul(&gradient, &res)?;
+                                let g2 = {
+                                    let rg = graph.sum_as(&g1, rdims)?;
+                                    let g = graph.tile_as(&rg, dims)?;
+                                    graph.mul(&g, &res)?
+                                };
+                                let grad = graph.sub(&g1, &g2)?;
+                                store.add_gradient::<D, _>(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
         }
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
@@ -7,7 +7,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
     Check, Eval, Number,
 };
 
@@ -188,14 +188,15 @@ impl CompareAlgebra<()> for Check {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims> CompareAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> CompareAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
                 + CoreAlgebra<D, Value = D>
                 + CompareAlgebra<D>
                 + LinkedAlgebra<Value<D>, D>,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
@@ -206,12 +207,14 @@ macro_rules! impl_graph {
                 r0: Option<&Value<D>>,
                 r1: Option<&Value<D>>,
             ) -> Result<Value<D>> {
-                let result = self.eval().select_argmax(
-                    v0.data(),
-                    v1.data(),
-                    r0.map(|r| r.data()),
-                    r1.map(|r| r.data()),
-                )?;
+                let result = self.eval_timed("select_argmax", |eval| {
+                    eval.select_argmax(
+                        v0.data(),
+                        v1.data(),
+                        r0.map(|r| r.data()),
+                        r1.map(|r| r.data()),
+                    )
+                })?;
                 let inputs = {
                     let mut i = Vec::new();
                     if let Some(r) = r0 {
@@ -222,30 +225,36 @@ macro_rules! impl_graph {
                     }
                     i
                 };
-                let value = self.make_node(result, inputs, {
-                    let v0 = v0.clone();
-                    let v1 = v1.clone();
-                    let id0 = r0.and_then(Value::id);
-                    let id1 = r1.and_then(Value::id);
-                    move |graph, store, gradient| {
-                        let c0 = graph.link(&v0);
-                        let c1 = graph.link(&v1);
-                        if let Some(id) = id0 {
-                            let grad = graph.select_argmax(c0, c1, Some(&gradient), None)?;
-                            store.add_gradient(graph, id, &grad)?;
+                let value = self.make_named_node(
+                    "select_argmax",
+                    result,
+                    inputs,
+                    v0.size_in_bytes() + v1.size_in_bytes(),
+                    {
+                        let v0 = v0.clone();
+                        let v1 = v1.clone();
+                        let id0 = r0.and_then(Value::id);
+                        let id1 = r1.and_then(Value::id);
+                        move |graph, store, gradient| {
+                            let c0 = graph.link(&v0);
+                            let c1 = graph.link(&v1);
+                            if let Some(id) = id0 {
+                                let grad = graph.select_argmax(c0, c1, Some(&gradient), None)?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            if let Some(id) = id1 {
+                                let grad = graph.select_argmax(c0, c1, None, Some(&gradient))?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        if let Some(id) = id1 {
-                            let grad = graph.select_argmax(c0, c1, None, Some(&gradient))?;
-                            store.add_gradient(graph, id, &grad)?;
-                        }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
         }
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
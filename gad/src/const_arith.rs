@@ -6,7 +6,7 @@ use crate::{
     core::{CoreAlgebra, HasDims},
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
     Check, Eval, Number,
 };
 
@@ -118,8 +118,8 @@ impl<C> ConstArithAlgebra<(), C> for Check {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims, C> ConstArithAlgebra<Value<D>, C> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims, C> ConstArithAlgebra<Value<D>, C> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
@@ -128,17 +128,18 @@ macro_rules! impl_graph {
                 + ConstArithAlgebra<D, C>
                 + LinkedAlgebra<Value<D>, D>,
             C: std::ops::Sub<C, Output = C> + num::One + Clone + 'static + Send + Sync,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
             fn setc(&mut self, v: &Value<D>, c: C) -> Value<D> {
-                let result = self.eval().addc(v.data(), c);
+                let result = self.eval_timed("setc", |eval| eval.setc(v.data(), c));
                 self.constant(result)
             }
 
             fn addc(&mut self, v: &Value<D>, c: C) -> Value<D> {
-                let result = self.eval().addc(v.data(), c);
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("addc", |eval| eval.addc(v.data(), c));
+                self.make_named_node("addc", result, vec![v.input()], 0, {
                     let id = v.id();
                     move |graph, store, gradient| {
                         if let Some(id) = id {
@@ -150,8 +151,8 @@ macro_rules! impl_graph {
             }
 
             fn mulc(&mut self, v: &Value<D>, c: C) -> Value<D> {
-                let result = self.eval().mulc(v.data(), c.clone());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("mulc", |eval| eval.mulc(v.data(), c.clone()));
+                self.make_named_node("mulc", result, vec![v.input()], 0, {
                     let id = v.id();
                     move |graph, store, gradient| {
                         if let Some(id) = id {
@@ -164,8 +165,8 @@ macro_rules! impl_graph {
             }
 
             fn powc(&mut self, v: &Value<D>, c: C) -> Value<D> {
-                let result = self.eval().powc(v.data(), c.clone());
-                self.make_node(result, vec![v.input()], {
+                let result = self.eval_timed("powc", |eval| eval.powc(v.data(), c.clone()));
+                self.make_named_node("powc", result, vec![v.input()], v.size_in_bytes(), {
                     let v = v.clone();
                     move |graph, store, gradient| {
                         if let Some(id) = v.id() {
@@ -183,5 +184,5 @@ macro_rules! impl_graph {
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
@@ -4,7 +4,7 @@
 use crate::{
     error::{Error, Result},
     graph::{Config1, ConfigN, Graph, Value},
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
     Check, Eval, Number,
 };
 
@@ -19,6 +19,15 @@ pub trait CoreAlgebra<Data> {
     /// A non-differential input to the computation.
     fn constant(&mut self, data: Data) -> Self::Value;
 
+    /// A non-differential copy of a value (aka "stop gradient").
+    /// * This is the identity for algebras without differentials.
+    fn detach(&mut self, value: &Self::Value) -> Self::Value
+    where
+        Self::Value: Clone,
+    {
+        value.clone()
+    }
+
     /// Compute the sum of two values `v1 + v2`.
     fn add(&mut self, v1: &Self::Value, v2: &Self::Value) -> Result<Self::Value>;
 
@@ -42,6 +51,28 @@ pub trait HasDims {
     type Dims;
 
     fn dims(&self) -> Self::Dims;
+
+    /// Whether all the numbers of the value are finite, that is, neither NaN nor infinite
+    /// (see [`Graph::enable_anomaly_detection`]).
+    /// * Values are assumed to be finite by default.
+    #[inline]
+    fn all_finite(&self) -> bool {
+        true
+    }
+
+    /// Euclidean norm of the value in `f64`, if available (see
+    /// [`Graph::to_dot_with_gradients`]).
+    #[inline]
+    fn norm(&self) -> Option<f64> {
+        None
+    }
+
+    /// Size in bytes of the data of the value (see [`Graph::enable_profiling`]).
+    /// * Defaults to the size of `Self`.
+    #[inline]
+    fn size_in_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
 }
 
 impl<A> HasDims for crate::graph::Value<A>
@@ -54,6 +85,21 @@ where
     fn dims(&self) -> Self::Dims {
         self.data().dims()
     }
+
+    #[inline]
+    fn all_finite(&self) -> bool {
+        self.data().all_finite()
+    }
+
+    #[inline]
+    fn norm(&self) -> Option<f64> {
+        self.data().norm()
+    }
+
+    #[inline]
+    fn size_in_bytes(&self) -> usize {
+        self.data().size_in_bytes()
+    }
 }
 
 impl<T: Number> HasDims for T {
@@ -61,6 +107,16 @@ impl<T: Number> HasDims for T {
 
     #[inline]
     fn dims(&self) {}
+
+    #[inline]
+    fn all_finite(&self) -> bool {
+        self.is_finite_number()
+    }
+
+    #[inline]
+    fn norm(&self) -> Option<f64> {
+        self.magnitude()
+    }
 }
 
 impl<T: HasDims> HasDims for std::sync::Arc<T> {
@@ -70,6 +126,21 @@ impl<T: HasDims> HasDims for std::sync::Arc<T> {
     fn dims(&self) -> Self::Dims {
         self.as_ref().dims()
     }
+
+    #[inline]
+    fn all_finite(&self) -> bool {
+        self.as_ref().all_finite()
+    }
+
+    #[inline]
+    fn norm(&self) -> Option<f64> {
+        self.as_ref().norm()
+    }
+
+    #[inline]
+    fn size_in_bytes(&self) -> usize {
+        self.as_ref().size_in_bytes()
+    }
 }
 
 impl<T: Number> CoreAlgebra<T> for Check {
@@ -124,6 +195,22 @@ mod af_core {
         fn dims(&self) -> af::Dim4 {
             self.dims()
         }
+
+        fn all_finite(&self) -> bool {
+            let (nan, _) = af::any_true_all(&af::isnan(self));
+            let (inf, _) = af::any_true_all(&af::isinf(self));
+            !nan && !inf
+        }
+
+        fn norm(&self) -> Option<f64> {
+            let values = self.cast::<f64>();
+            Some(af::norm(&values, af::NormType::VECTOR_2, 0.0, 0.0))
+        }
+
+        #[inline]
+        fn size_in_bytes(&self) -> usize {
+            self.elements() * std::mem::size_of::<T>()
+        }
     }
 
     impl HasDims for af::Dim4 {
@@ -201,10 +288,11 @@ mod af_core {
 // }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims> CoreAlgebra<D> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> CoreAlgebra<D> for Graph<$config<E, S>>
         where
             E: Default + Clone + CoreAlgebra<D, Value = D>,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
@@ -218,9 +306,13 @@ macro_rules! impl_graph {
                 Value::constant(data)
             }
 
+            fn detach(&mut self, value: &Value<D>) -> Value<D> {
+                Value::constant(value.data().clone())
+            }
+
             fn add(&mut self, v1: &Value<D>, v2: &Value<D>) -> Result<Value<D>> {
-                let result = self.eval().add(v1.data(), v2.data())?;
-                let value = self.make_node(result, vec![v1.input(), v2.input()], {
+                let result = self.eval_timed("add", |eval| eval.add(v1.data(), v2.data()))?;
+                let value = self.make_named_node("add", result, vec![v1.input(), v2.input()], 0, {
                     let id1 = v1.id();
                     let id2 = v2.id();
                     move |graph, store, gradient| {
@@ -237,11 +329,10 @@ macro_rules! impl_graph {
             }
 
             fn add_all(&mut self, values: &[&Value<D>]) -> Result<Value<D>> {
-                let result = self
-                    .eval()
-                    .add_all(&values.iter().map(|v| v.data()).collect::<Vec<_>>())?;
+                let data = values.iter().map(|v| v.data()).collect::<Vec<_>>();
+                let result = self.eval_timed("add_all", |eval| eval.add_all(&data))?;
                 let inputs = values.iter().map(|v| v.input()).collect::<Vec<_>>();
-                let value = self.make_node(result, inputs, {
+                let value = self.make_named_node("add_all", result, inputs, 0, {
                     let ids = values.iter().map(|v| v.id()).collect::<Vec<_>>();
                     move |graph, store, gradient| {
                         for id in &ids {
@@ -258,5 +349,5 @@ macro_rules! impl_graph {
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
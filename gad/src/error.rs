@@ -1,6 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::store::Id;
 use backtrace::Backtrace;
 use std::fmt::Debug;
 use thiserror::Error;
@@ -37,6 +38,52 @@ pub enum Error {
     MissingGradient { name: String, trace: String },
     #[error("Trying to obtain a node from an incorrect `id`.")]
     MissingNode { name: String, trace: String },
+    #[error("Trying to use a value of another graph or tape in {name} (node {node:?})\n{trace}")]
+    ForeignNode {
+        name: String,
+        node: Option<Id>,
+        trace: String,
+    },
+    #[error("Trying to use a value of another trace in {name}\n{trace}")]
+    ForeignTrace { name: String, trace: String },
+    #[error("Trying to evaluate a sub-network more than once.")]
+    RepeatedEvaluation { name: String, trace: String },
+    #[error("Incorrect type of gradient for {name}: expected {expected}, found {actual}\n{trace}")]
+    GradientType {
+        name: String,
+        expected: String,
+        actual: String,
+        trace: String,
+    },
+    #[error(
+        "Non-finite {kind} (NaN or infinity) produced by operator `{op}` at {}\n{trace}",
+        .node.map_or_else(|| "a constant".to_string(), |id| format!("node {:?}", id))
+    )]
+    Anomaly {
+        name: String,
+        op: String,
+        node: Option<Id>,
+        kind: AnomalyKind,
+        trace: String,
+    },
+}
+
+/// The kind of non-finite number reported by [`Error::Anomaly`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// A forward value.
+    Value,
+    /// A gradient computed during backward propagation.
+    Gradient,
+}
+
+impl std::fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnomalyKind::Value => write!(f, "value"),
+            AnomalyKind::Gradient => write!(f, "gradient"),
+        }
+    }
 }
 
 /// Default result type for the crate.
@@ -133,6 +180,52 @@ impl Error {
             trace: Self::backtrace(),
         }
     }
+
+    /// Report a value that belongs to another graph or tape, with its node if known.
+    pub fn foreign_node(name: &str, node: Option<Id>) -> Self {
+        Error::ForeignNode {
+            name: name.to_string(),
+            node,
+            trace: Self::backtrace(),
+        }
+    }
+
+    /// Report a value that was recorded by another tracer.
+    pub fn foreign_trace(name: &str) -> Self {
+        Error::ForeignTrace {
+            name: name.to_string(),
+            trace: Self::backtrace(),
+        }
+    }
+
+    /// Report a repeated evaluation.
+    pub fn repeated_evaluation(name: &str) -> Self {
+        Error::RepeatedEvaluation {
+            name: name.to_string(),
+            trace: Self::backtrace(),
+        }
+    }
+
+    /// Report a gradient of unexpected type.
+    pub fn gradient_type(name: &str, expected: &str, actual: &str) -> Self {
+        Error::GradientType {
+            name: name.to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+            trace: Self::backtrace(),
+        }
+    }
+
+    /// Report a non-finite value (or gradient) produced by an operator.
+    pub fn anomaly(name: &str, op: &str, node: Option<Id>, kind: AnomalyKind) -> Self {
+        Error::Anomaly {
+            name: name.to_string(),
+            op: op.to_string(),
+            node,
+            kind,
+            trace: Self::backtrace(),
+        }
+    }
 }
 
 /// Check that all the given dimensions are equal.
@@ -0,0 +1,458 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    analytic::AnalyticAlgebra,
+    arith::ArithAlgebra,
+    array::ArrayAlgebra,
+    compare::CompareAlgebra,
+    const_arith::ConstArithAlgebra,
+    core::{CoreAlgebra, HasDims},
+    error::{check_equal_dimensions, Result},
+    matrix::{MatProp, MatrixAlgebra},
+};
+
+/// An algebra computing first-order differentials in forward mode on top of an
+/// evaluation algebra `E` (e.g. `Eval`).
+///
+/// Values carry a tangent alongside their primal data. Contrary to `Graph`, no tape is
+/// recorded: differentials are propagated during the forward computation itself.
+#[derive(Clone, Default)]
+pub struct Forward<E> {
+    eval: E,
+}
+
+/// A value tracked by the algebra `Forward`, aka "dual number".
+/// A missing tangent stands for zero.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Dual<D> {
+    data: D,
+    tangent: Option<D>,
+}
+
+impl<D> Dual<D> {
+    /// A value with a zero tangent.
+    #[inline]
+    pub fn constant(data: D) -> Self {
+        Self {
+            data,
+            tangent: None,
+        }
+    }
+
+    #[inline]
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    #[inline]
+    pub fn tangent(&self) -> Option<&D> {
+        self.tangent.as_ref()
+    }
+}
+
+impl<D: HasDims> HasDims for Dual<D> {
+    type Dims = D::Dims;
+
+    #[inline]
+    fn dims(&self) -> Self::Dims {
+        self.data.dims()
+    }
+}
+
+impl<E: Default> Forward<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<E> Forward<E> {
+    /// Access the underlying evaluation algebra.
+    #[inline]
+    pub fn eval(&mut self) -> &mut E {
+        &mut self.eval
+    }
+
+    /// A differential input to the computation with the given tangent (aka direction).
+    pub fn dual<D>(&mut self, data: D, tangent: D) -> Result<Dual<D>>
+    where
+        E: CoreAlgebra<D, Value = D>,
+        D: HasDims,
+        D::Dims: PartialEq + std::fmt::Debug + Clone,
+    {
+        check_equal_dimensions(func_name!(), &[&data.dims(), &tangent.dims()])?;
+        Ok(Dual {
+            data: self.eval.variable(data),
+            tangent: Some(self.eval.variable(tangent)),
+        })
+    }
+
+    /// Sum two optional tangents.
+    fn add_tangents<D>(&mut self, t0: Option<D>, t1: Option<D>) -> Result<Option<D>>
+    where
+        E: CoreAlgebra<D, Value = D>,
+    {
+        match (t0, t1) {
+            (Some(t0), Some(t1)) => Ok(Some(self.eval.add(&t0, &t1)?)),
+            (t, None) | (None, t) => Ok(t),
+        }
+    }
+
+    /// Apply a fallible linear map to an optional tangent.
+    fn map_tangent<D, R, F>(&mut self, t: Option<&D>, f: F) -> Result<Option<R>>
+    where
+        F: FnOnce(&mut E, &D) -> Result<R>,
+    {
+        match t {
+            Some(t) => Ok(Some(f(&mut self.eval, t)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<D, E> CoreAlgebra<D> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D>,
+    D: Clone,
+{
+    type Value = Dual<D>;
+
+    /// Variables have a zero tangent until a direction is given with `dual`.
+    #[inline]
+    fn variable(&mut self, data: D) -> Dual<D> {
+        Dual::constant(self.eval.variable(data))
+    }
+
+    #[inline]
+    fn constant(&mut self, data: D) -> Dual<D> {
+        Dual::constant(self.eval.constant(data))
+    }
+
+    #[inline]
+    fn detach(&mut self, value: &Dual<D>) -> Dual<D> {
+        Dual::constant(value.data.clone())
+    }
+
+    fn add(&mut self, v0: &Dual<D>, v1: &Dual<D>) -> Result<Dual<D>> {
+        let data = self.eval.add(&v0.data, &v1.data)?;
+        let tangent = self.add_tangents(v0.tangent.clone(), v1.tangent.clone())?;
+        Ok(Dual { data, tangent })
+    }
+}
+
+impl<D, E> ArithAlgebra<Dual<D>> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D> + ArithAlgebra<D>,
+    D: Clone,
+{
+    #[inline]
+    fn zeros(&mut self, v: &Dual<D>) -> Dual<D> {
+        Dual::constant(self.eval.zeros(&v.data))
+    }
+
+    #[inline]
+    fn ones(&mut self, v: &Dual<D>) -> Dual<D> {
+        Dual::constant(self.eval.ones(&v.data))
+    }
+
+    fn neg(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.neg(&v.data);
+        let tangent = v.tangent.as_ref().map(|t| self.eval.neg(t));
+        Dual { data, tangent }
+    }
+
+    fn sub(&mut self, v0: &Dual<D>, v1: &Dual<D>) -> Result<Dual<D>> {
+        let data = self.eval.sub(&v0.data, &v1.data)?;
+        let tangent = match (&v0.tangent, &v1.tangent) {
+            (Some(t0), Some(t1)) => Some(self.eval.sub(t0, t1)?),
+            (Some(t0), None) => Some(t0.clone()),
+            (None, Some(t1)) => Some(self.eval.neg(t1)),
+            (None, None) => None,
+        };
+        Ok(Dual { data, tangent })
+    }
+
+    fn mul(&mut self, v0: &Dual<D>, v1: &Dual<D>) -> Result<Dual<D>> {
+        let data = self.eval.mul(&v0.data, &v1.data)?;
+        let t0 = self.map_tangent(v0.tangent(), |e, t| e.mul(t, &v1.data))?;
+        let t1 = self.map_tangent(v1.tangent(), |e, t| e.mul(&v0.data, t))?;
+        let tangent = self.add_tangents(t0, t1)?;
+        Ok(Dual { data, tangent })
+    }
+}
+
+impl<D, E> AnalyticAlgebra<Dual<D>> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D> + ArithAlgebra<D> + AnalyticAlgebra<D>,
+    D: Clone,
+{
+    fn exp(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.exp(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| e.mul(t, &data))
+            .expect("exp should not fail");
+        Dual { data, tangent }
+    }
+
+    fn log(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.log(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| e.div(t, &v.data))
+            .expect("log should not fail");
+        Dual { data, tangent }
+    }
+
+    fn log1p(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.log1p(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let one = e.ones(&v.data);
+                let v1p = e.add(&one, &v.data)?;
+                e.div(t, &v1p)
+            })
+            .expect("log1p should not fail");
+        Dual { data, tangent }
+    }
+
+    fn sin(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.sin(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let k = e.cos(&v.data);
+                e.mul(t, &k)
+            })
+            .expect("sin should not fail");
+        Dual { data, tangent }
+    }
+
+    fn cos(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.cos(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let c = e.sin(&v.data);
+                let k = e.neg(&c);
+                e.mul(t, &k)
+            })
+            .expect("cos should not fail");
+        Dual { data, tangent }
+    }
+
+    fn tanh(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.tanh(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let one = e.ones(&data);
+                let c = e.mul(&data, &data)?;
+                let k = e.sub(&one, &c)?;
+                e.mul(t, &k)
+            })
+            .expect("tanh should not fail");
+        Dual { data, tangent }
+    }
+
+    fn sigmoid(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.sigmoid(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let one = e.ones(&data);
+                let d = e.sub(&one, &data)?;
+                let k = e.mul(&data, &d)?;
+                e.mul(t, &k)
+            })
+            .expect("sigmoid should not fail");
+        Dual { data, tangent }
+    }
+
+    fn reciprocal(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.reciprocal(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let c = e.mul(&data, &data)?;
+                let k = e.neg(&c);
+                e.mul(t, &k)
+            })
+            .expect("reciprocal should not fail");
+        Dual { data, tangent }
+    }
+
+    fn sqrt(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.sqrt(&v.data);
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let c = e.add(&data, &data)?;
+                e.div(t, &c)
+            })
+            .expect("sqrt should not fail");
+        Dual { data, tangent }
+    }
+
+    fn div(&mut self, v0: &Dual<D>, v1: &Dual<D>) -> Result<Dual<D>> {
+        let data = self.eval.div(&v0.data, &v1.data)?;
+        let t0 = self.map_tangent(v0.tangent(), |e, t| e.div(t, &v1.data))?;
+        let t1 = self.map_tangent(v1.tangent(), |e, t| {
+            let c = e.mul(&data, t)?;
+            let c = e.div(&c, &v1.data)?;
+            Ok(e.neg(&c))
+        })?;
+        let tangent = self.add_tangents(t0, t1)?;
+        Ok(Dual { data, tangent })
+    }
+}
+
+impl<D, E, C> ConstArithAlgebra<Dual<D>, C> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D> + ArithAlgebra<D> + ConstArithAlgebra<D, C>,
+    C: std::ops::Sub<C, Output = C> + num::One + Clone,
+    D: Clone,
+{
+    #[inline]
+    fn setc(&mut self, v: &Dual<D>, c: C) -> Dual<D> {
+        Dual::constant(self.eval.setc(&v.data, c))
+    }
+
+    fn addc(&mut self, v: &Dual<D>, c: C) -> Dual<D> {
+        let data = self.eval.addc(&v.data, c);
+        Dual {
+            data,
+            tangent: v.tangent.clone(),
+        }
+    }
+
+    fn mulc(&mut self, v: &Dual<D>, c: C) -> Dual<D> {
+        let data = self.eval.mulc(&v.data, c.clone());
+        let tangent = v.tangent.as_ref().map(|t| self.eval.mulc(t, c));
+        Dual { data, tangent }
+    }
+
+    fn powc(&mut self, v: &Dual<D>, c: C) -> Dual<D> {
+        let data = self.eval.powc(&v.data, c.clone());
+        let tangent = self
+            .map_tangent(v.tangent(), |e, t| {
+                let p = e.powc(&v.data, c.clone() - C::one());
+                let k = e.mulc(&p, c);
+                e.mul(t, &k)
+            })
+            .expect("powc should not fail");
+        Dual { data, tangent }
+    }
+}
+
+impl<D, E> CompareAlgebra<Dual<D>> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D> + CompareAlgebra<D>,
+    D: Clone,
+{
+    fn select_argmax(
+        &mut self,
+        v0: &Dual<D>,
+        v1: &Dual<D>,
+        r0: Option<&Dual<D>>,
+        r1: Option<&Dual<D>>,
+    ) -> Result<Dual<D>> {
+        let data = self.eval.select_argmax(
+            &v0.data,
+            &v1.data,
+            r0.map(|r| &r.data),
+            r1.map(|r| &r.data),
+        )?;
+        let t0 = r0.and_then(Dual::tangent);
+        let t1 = r1.and_then(Dual::tangent);
+        let tangent = if t0.is_some() || t1.is_some() {
+            Some(self.eval.select_argmax(&v0.data, &v1.data, t0, t1)?)
+        } else {
+            None
+        };
+        Ok(Dual { data, tangent })
+    }
+}
+
+impl<D, E, T, Dims> ArrayAlgebra<Dual<D>> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D>
+        + CoreAlgebra<T, Value = T>
+        + ArrayAlgebra<D, Scalar = T, Dims = Dims>,
+    D: Clone,
+    T: Clone,
+    Dims: Clone,
+{
+    type Dims = Dims;
+    type Scalar = Dual<T>;
+
+    fn flat(&mut self, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.flat(&v.data);
+        let tangent = v.tangent.as_ref().map(|t| self.eval.flat(t));
+        Dual { data, tangent }
+    }
+
+    fn moddims(&mut self, v: &Dual<D>, dims: Dims) -> Result<Dual<D>> {
+        let data = self.eval.moddims(&v.data, dims.clone())?;
+        let tangent = self.map_tangent(v.tangent(), |e, t| e.moddims(t, dims))?;
+        Ok(Dual { data, tangent })
+    }
+
+    fn tile_as(&mut self, v: &Dual<D>, dims: Dims) -> Result<Dual<D>> {
+        let data = self.eval.tile_as(&v.data, dims.clone())?;
+        let tangent = self.map_tangent(v.tangent(), |e, t| e.tile_as(t, dims))?;
+        Ok(Dual { data, tangent })
+    }
+
+    fn sum_as(&mut self, v: &Dual<D>, dims: Dims) -> Result<Dual<D>> {
+        let data = self.eval.sum_as(&v.data, dims.clone())?;
+        let tangent = self.map_tangent(v.tangent(), |e, t| e.sum_as(t, dims))?;
+        Ok(Dual { data, tangent })
+    }
+
+    fn constant_as(&mut self, v: &Dual<T>, dims: Dims) -> Dual<D> {
+        let data = self.eval.constant_as(&v.data, dims.clone());
+        let tangent = v.tangent.as_ref().map(|t| self.eval.constant_as(t, dims));
+        Dual { data, tangent }
+    }
+
+    fn as_scalar(&mut self, v: &Dual<D>) -> Result<Dual<T>> {
+        let data = self.eval.as_scalar(&v.data)?;
+        let tangent = self.map_tangent(v.tangent(), |e, t| e.as_scalar(t))?;
+        Ok(Dual { data, tangent })
+    }
+
+    fn scale(&mut self, lambda: &Dual<T>, v: &Dual<D>) -> Dual<D> {
+        let data = self.eval.scale(&lambda.data, &v.data);
+        let t0 = lambda.tangent.as_ref().map(|t| self.eval.scale(t, &v.data));
+        let t1 = v.tangent.as_ref().map(|t| self.eval.scale(&lambda.data, t));
+        let tangent = self.add_tangents(t0, t1).expect("scale should not fail");
+        Dual { data, tangent }
+    }
+
+    fn dot(&mut self, v1: &Dual<D>, v2: &Dual<D>) -> Result<Dual<T>> {
+        let data = self.eval.dot(&v1.data, &v2.data)?;
+        let t1 = self.map_tangent(v1.tangent(), |e, t| e.dot(t, &v2.data))?;
+        let t2 = self.map_tangent(v2.tangent(), |e, t| e.dot(&v1.data, t))?;
+        let tangent = self.add_tangents(t1, t2)?;
+        Ok(Dual { data, tangent })
+    }
+}
+
+impl<D, E> MatrixAlgebra<Dual<D>> for Forward<E>
+where
+    E: CoreAlgebra<D, Value = D> + MatrixAlgebra<D>,
+    D: Clone,
+{
+    fn matmul(
+        &mut self,
+        v1: &Dual<D>,
+        v2: &Dual<D>,
+        prop1: MatProp,
+        prop2: MatProp,
+    ) -> Result<Dual<D>> {
+        let data = self.eval.matmul(&v1.data, &v2.data, prop1, prop2)?;
+        let t1 = self.map_tangent(v1.tangent(), |e, t| e.matmul(t, &v2.data, prop1, prop2))?;
+        let t2 = self.map_tangent(v2.tangent(), |e, t| e.matmul(&v1.data, t, prop1, prop2))?;
+        let tangent = self.add_tangents(t1, t2)?;
+        Ok(Dual { data, tangent })
+    }
+
+    fn transpose(&mut self, v: &Dual<D>, conjugate: bool) -> Result<Dual<D>> {
+        let data = self.eval.transpose(&v.data, conjugate)?;
+        let tangent = self.map_tangent(v.tangent(), |e, t| e.transpose(t, conjugate))?;
+        Ok(Dual { data, tangent })
+    }
+}
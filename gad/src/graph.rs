@@ -2,16 +2,22 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    arith::ArithAlgebra,
     core::{CoreAlgebra, HasDims},
-    error::{check_equal_dimensions, Error, Result},
+    error::{check_equal_dimensions, check_equal_lengths, AnomalyKind, Error, Result},
+    profile::{Profile, Profiler},
     store::{
-        GenericGradientMap1, GenericGradientMapN, GradientId, GradientStore, GraphArenaBehavior, Id,
+        GenericGradientMap1, GenericGradientMapN, GradientId, GradientReader, GradientStore,
+        GradientStore1, GradientStoreN, GraphArenaBehavior, Id,
     },
 };
-use std::{collections::BinaryHeap, sync::Arc};
+use std::{
+    collections::{BTreeSet, BinaryHeap},
+    sync::Arc,
+};
 
 #[cfg(doc)]
-use crate::prelude::*;
+use crate::{prelude::*, store::*};
 
 /// Main structure holding the computational graph (aka "tape") used for automatic differentiation.
 /// In practice, the configuration is instantiated to build either [`Graph1`] or [`GraphN`],
@@ -19,6 +25,26 @@ use crate::prelude::*;
 pub struct Graph<C: Config> {
     nodes: id_arena::Arena<Node<C>, GraphArenaBehavior>,
     eval: C::EvalAlgebra,
+    /// Whether new nodes are recorded (see [`Graph::no_grad`]).
+    recording: bool,
+    /// Optional profiling statistics (see [`Graph::enable_profiling`]).
+    profiler: Option<Profiler>,
+    /// Whether values and gradients are checked (see [`Graph::enable_anomaly_detection`]).
+    detect_anomalies: bool,
+    /// First non-finite forward value found by anomaly detection, if any.
+    anomaly: Option<Error>,
+}
+
+/// Restore the recording mode of a graph when dropped (see [`Graph::no_grad`]).
+struct RecordingGuard<'a, C: Config> {
+    graph: &'a mut Graph<C>,
+    recording: bool,
+}
+
+impl<C: Config> Drop for RecordingGuard<'_, C> {
+    fn drop(&mut self) {
+        self.graph.recording = self.recording;
+    }
 }
 
 /// Configuration trait for `Graph`.
@@ -48,6 +74,31 @@ pub struct Node<C: Config> {
     inputs: Vec<Option<Id>>,
     /// Function for updating the gradient of the input variables.
     update_func: Option<GradientUpdateFunc<C>>,
+    /// Function for adding the gradient of this node found in another store.
+    merge_func: GradientMergeFunc<C>,
+    /// Function for checking that the gradient of this node is finite.
+    check_func: GradientCheckFunc<C>,
+    /// Function for computing the norm of the gradient of this node.
+    norm_func: GradientNormFunc<C>,
+    /// Functions called on the final gradient of this node.
+    hooks: Vec<GradientHookFunc<C>>,
+    /// Error reported by the gradient computations that reach this node, if any.
+    error: Option<Error>,
+    /// Description of the node.
+    info: NodeInfo,
+}
+
+/// Metadata recorded for each computational node.
+#[derive(Clone)]
+pub struct NodeInfo {
+    /// Name of the operator that created the node.
+    op: &'static str,
+    /// Dimensions of the data of the node.
+    dims: Arc<dyn std::fmt::Debug + Send + Sync>,
+    /// Type of the data of the node.
+    type_name: &'static str,
+    /// Optional user label.
+    label: Option<String>,
 }
 
 type GradientUpdateFunc<C> = Arc<
@@ -61,10 +112,101 @@ type GradientUpdateFunc<C> = Arc<
         + Sync,
 >;
 
+type GradientHookFunc<C> = Arc<
+    dyn Fn(
+            /* algebra to for gradient computation */
+            &mut <C as Config>::GradientAlgebra,
+            /* store */ &mut <C as Config>::GradientStore,
+            /* index of the gradient in the store */ Id,
+        ) -> Result<()>
+        + Send
+        + Sync,
+>;
+
+type GradientMergeFunc<C> = fn(
+    /* algebra to for gradient computation */ &mut <C as Config>::GradientAlgebra,
+    /* target store */ &mut <C as Config>::GradientStore,
+    /* source store */ &<C as Config>::GradientStore,
+    /* index of the gradient in both stores */ Id,
+) -> Result<()>;
+
+type GradientCheckFunc<C> = fn(
+    /* store */ &<C as Config>::GradientStore,
+    /* index of the gradient in the store */ Id,
+) -> bool;
+
+type GradientNormFunc<C> = fn(
+    /* store */ &<C as Config>::GradientStore,
+    /* index of the gradient in the store */ Id,
+) -> Option<Option<f64>>;
+
 impl<C: Config> Node<C> {
     fn clear(&mut self) {
         self.inputs.clear();
         self.update_func = None;
+        self.hooks.clear();
+    }
+
+    /// Whether `backward` does anything.
+    #[cfg(feature = "rayon")]
+    fn has_backward(&self) -> bool {
+        self.update_func.is_some() || !self.hooks.is_empty() || self.error.is_some()
+    }
+
+    /// Report the error of the node, if any, otherwise run the hooks of the node `id` then
+    /// propagate its gradient to the inputs.
+    #[inline]
+    fn backward(
+        &self,
+        graph: &mut C::GradientAlgebra,
+        store: &mut C::GradientStore,
+        id: Id,
+    ) -> Result<()> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        for hook in &self.hooks {
+            hook(graph, store, id)?;
+        }
+        if let Some(update_func) = &self.update_func {
+            update_func(graph, store, id)?;
+        }
+        Ok(())
+    }
+}
+
+impl NodeInfo {
+    fn new<D, Dims>(op: &'static str, data: &D) -> Self
+    where
+        D: HasDims<Dims = Dims>,
+        Dims: std::fmt::Debug + 'static + Send + Sync,
+    {
+        Self {
+            op,
+            dims: Arc::new(data.dims()),
+            type_name: std::any::type_name::<D>(),
+            label: None,
+        }
+    }
+
+    /// Name of the operator that created the node (e.g. "mul"), or "variable".
+    pub fn op(&self) -> &'static str {
+        self.op
+    }
+
+    /// Dimensions of the data of the node.
+    pub fn dims(&self) -> &dyn std::fmt::Debug {
+        &*self.dims
+    }
+
+    /// Name of the type of the data of the node.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// User label of the node, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
     }
 }
 
@@ -80,6 +222,10 @@ impl<C: Config> Graph<C> {
         Self {
             nodes: id_arena::Arena::new(),
             eval: C::EvalAlgebra::default(),
+            recording: true,
+            profiler: None,
+            detect_anomalies: false,
+            anomaly: None,
         }
     }
 
@@ -87,22 +233,277 @@ impl<C: Config> Graph<C> {
     pub fn eval(&mut self) -> &mut C::EvalAlgebra {
         &mut self.eval
     }
+
+    /// Compute a forward value with the evaluation algebra, like [`Graph::eval`], and count the
+    /// time spent in `f` as the forward time of the operator `op` when profiling is enabled.
+    #[inline]
+    pub fn eval_timed<R, F>(&mut self, op: &'static str, f: F) -> R
+    where
+        F: FnOnce(&mut C::EvalAlgebra) -> R,
+    {
+        let eval = &mut self.eval;
+        match &self.profiler {
+            Some(profiler) => profiler.time_forward(op, || f(eval)),
+            None => f(eval),
+        }
+    }
+
+    /// Start collecting statistics on the nodes created from now on: number of nodes, size of
+    /// the values retained by their gradient-update closures, and time spent in forward (see [`Graph::eval_timed`]) and backward
+    /// computations (see [`Profile`]).
+    /// * Clones of the graph share the same statistics.
+    pub fn enable_profiling(&mut self) {
+        if self.profiler.is_none() {
+            self.profiler = Some(Profiler::default());
+        }
+    }
+
+    /// The statistics collected so far, if profiling is enabled.
+    pub fn profile(&self) -> Option<Profile> {
+        self.profiler.as_ref().map(Profiler::profile)
+    }
+
+    /// Start checking that the values and the gradients of the nodes created from now on are
+    /// finite, that is, neither NaN nor infinite (see [`HasDims::all_finite`]).
+    /// * The first non-finite forward value is reported by [`Graph::check_anomalies`] until
+    /// [`Graph::clear_anomalies`] is called.
+    /// * Gradient computations fail when they reach a node whose value is not finite.
+    /// * During backward propagation, the first non-finite gradient stops the computation
+    /// and is reported together with the operator and the node that produced it.
+    pub fn enable_anomaly_detection(&mut self) {
+        self.detect_anomalies = true;
+    }
+
+    /// Report the first non-finite forward value found by anomaly detection, if any.
+    pub fn check_anomalies(&self) -> Result<()> {
+        match &self.anomaly {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the non-finite forward value reported by [`Graph::check_anomalies`], if any.
+    /// * Existing nodes with a non-finite value still fail the gradient computations that
+    /// reach them.
+    pub fn clear_anomalies(&mut self) {
+        self.anomaly = None;
+    }
+
+    /// Run `f` without recording computation nodes: variables and the results of all
+    /// operations are constants. The previous mode is restored afterwards, even if `f` panics.
+    pub fn no_grad<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let recording = std::mem::replace(&mut self.recording, false);
+        let guard = RecordingGuard {
+            graph: self,
+            recording,
+        };
+        f(&mut *guard.graph)
+    }
+
+    /// Register a function to be called on the gradient of the node `id` during backward
+    /// propagation, once the gradient is fully accumulated and before it is propagated to
+    /// the inputs of the node.
+    /// * The hook may inspect, modify, or replace the gradient (e.g. for clipping).
+    /// * Hooks are called in the order of registration.
+    pub fn register_hook<D, G, F>(&mut self, id: GradientId<D>, hook: F) -> Result<()>
+    where
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        F: Fn(&mut C::GradientAlgebra, &mut G) -> Result<()> + 'static + Send + Sync,
+    {
+        self.check_ids(func_name!(), Some(&id.inner))?;
+        let node = self
+            .nodes
+            .get_mut(id.inner)
+            .ok_or_else(|| Error::missing_node(func_name!()))?;
+        node.hooks.push(Arc::new(move |graph, store, index| {
+            match store.try_get_mut(GradientId::<D>::new(index))? {
+                Some(gradient) => hook(graph, gradient),
+                None => Ok(()),
+            }
+        }));
+        Ok(())
+    }
+
+    /// Whether new computation nodes are currently recorded (see [`Graph::no_grad`]).
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Metadata of the computation node `id`, if any.
+    pub fn node_info(&self, id: Id) -> Option<&NodeInfo> {
+        Some(&self.nodes.get(id)?.info)
+    }
+
+    /// Attach a label to the computation node of a value (no-op for constants).
+    pub fn label<D>(&mut self, value: &Value<D>, label: impl Into<String>) {
+        if let Some(node) = value.input().and_then(|id| self.nodes.get_mut(id)) {
+            node.info.label = Some(label.into());
+        }
+    }
+
+    /// Export the computational graph in the DOT format of Graphviz.
+    /// * Variables are drawn as boxes and operations as ellipses, labelled with
+    /// the operator name, the user label (if any), and the dimensions of the node.
+    /// * Constant inputs are not part of the graph and are drawn as `const` leaves.
+    pub fn to_dot(&self) -> String {
+        self.write_dot(None)
+    }
+
+    /// Same as [`Graph::to_dot`] but annotate each node with the norm of its gradient in
+    /// `store` (see [`HasDims::norm`]). Nodes without a gradient are drawn in gray, nodes with
+    /// a non-finite gradient in red.
+    pub fn to_dot_with_gradients(&self, store: &C::GradientStore) -> String {
+        self.write_dot(Some(store))
+    }
+
+    fn write_dot(&self, store: Option<&C::GradientStore>) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::from("digraph {\n");
+        for (id, node) in self.nodes.iter() {
+            let info = &node.info;
+            let mut label = info.op.to_string();
+            if let Some(name) = &info.label {
+                write!(label, " \"{}\"", name).unwrap();
+            }
+            write!(label, "\n{} {:?}", info.type_name, info.dims).unwrap();
+            let shape = if node.update_func.is_none() && node.inputs.is_empty() {
+                "box"
+            } else {
+                "ellipse"
+            };
+            let color = match store {
+                None => "black",
+                Some(store) => match (node.norm_func)(store, id) {
+                    None => "gray",
+                    Some(norm) => {
+                        match norm {
+                            Some(n) => write!(label, "\n|grad| = {:e}", n).unwrap(),
+                            None => label.push_str("\n|grad| = ?"),
+                        }
+                        if (node.check_func)(store, id) {
+                            "black"
+                        } else {
+                            "red"
+                        }
+                    }
+                },
+            };
+            writeln!(
+                out,
+                "  n{} [label={:?}, shape={}, color={}];",
+                id.index(),
+                label,
+                shape,
+                color
+            )
+            .unwrap();
+            for (k, input) in node.inputs.iter().enumerate() {
+                match input {
+                    Some(input) => {
+                        writeln!(out, "  n{} -> n{};", input.index(), id.index()).unwrap()
+                    }
+                    None => {
+                        writeln!(
+                            out,
+                            "  c{}_{} [label=\"const\", shape=plaintext];\n  c{}_{} -> n{};",
+                            id.index(),
+                            k,
+                            id.index(),
+                            k,
+                            id.index()
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
 }
 
 impl<C: Config> Graph<C> {
     #[inline]
-    pub(crate) fn make_variable<D>(&mut self, data: D) -> Value<D> {
+    pub(crate) fn make_variable<D, G, Dims>(&mut self, data: D) -> Value<D>
+    where
+        C::GradientAlgebra: CoreAlgebra<D, Value = G>,
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        D: HasDims<Dims = Dims>,
+        G: HasDims + Clone + 'static,
+        Dims: std::fmt::Debug + 'static + Send + Sync,
+    {
+        if !self.recording {
+            self.check_value("variable", &data, None);
+            return Value::constant(data);
+        }
+        if let Some(profiler) = &self.profiler {
+            profiler.record_node("variable", 0);
+        }
         let node = Node {
             inputs: Vec::new(),
             update_func: None,
+            merge_func: Self::merge_gradient::<D, G>,
+            check_func: Self::check_gradient::<D, G>,
+            norm_func: Self::gradient_norm::<D, G>,
+            hooks: Vec::new(),
+            error: None,
+            info: NodeInfo::new("variable", &data),
         };
-        let id = Some(GradientId::new(self.nodes.alloc(node)));
+        let id = self.nodes.alloc(node);
+        self.check_value("variable", &data, Some(id));
+        let id = Some(GradientId::new(id));
         Value { id, data }
     }
 
+    /// Create a node without inputs that holds a gradient of type `W` written by the
+    /// gradient-update function of another node (e.g. the weight gradients of a
+    /// [`Checkpoint`](crate::net::Checkpoint) network).
+    /// * The node must be an input of the node that writes the gradient.
+    /// * Returns `None` when nodes are not recorded.
+    pub(crate) fn make_gradient_slot<W>(&mut self, op: &'static str) -> Option<GradientId<W>>
+    where
+        C::GradientStore: GradientStore<GradientId<W>, W>,
+        W: Clone + 'static,
+    {
+        if !self.recording {
+            return None;
+        }
+        if let Some(profiler) = &self.profiler {
+            profiler.record_node(op, 0);
+        }
+        let node = Node {
+            inputs: Vec::new(),
+            update_func: None,
+            merge_func: Self::merge_slot::<W>,
+            check_func: |_, _| true,
+            norm_func: |store: &C::GradientStore, id| {
+                let gradient = store.try_get(GradientId::<W>::new(id)).ok()?;
+                gradient.map(|_| None)
+            },
+            hooks: Vec::new(),
+            error: None,
+            info: NodeInfo {
+                op,
+                dims: Arc::new(()),
+                type_name: std::any::type_name::<W>(),
+                label: None,
+            },
+        };
+        Some(GradientId::new(self.nodes.alloc(node)))
+    }
+
     /// Create a computation node (used to define operators).
     /// During back-propagation, `update_func` must call `store.add_gradient` to propagate the gradient
     /// of each (non-constant) input.
+    /// * If one of the `inputs` belongs to another graph, the error [`Error::ForeignNode`] is
+    /// reported by the gradient computations that reach the new node.
+    /// * The node is recorded under the operator name `"custom"` with no retained bytes (see
+    /// [`Graph::make_named_node`]).
     pub fn make_node<D, G, F, Dims>(
         &mut self,
         data: D,
@@ -120,10 +521,44 @@ impl<C: Config> Graph<C> {
             + Send
             + Sync,
     {
-        self.make_generic_node::<D, D, G, G, F, Dims>(data, inputs, update_func)
+        self.make_named_node("custom", data, inputs, 0, update_func)
+    }
+
+    /// Same as [`Graph::make_node`] but records the name of the operator `op` for
+    /// debugging purposes.
+    /// * `retained_bytes` is the size of the values captured by `update_func`, as reported by
+    /// profiling (see [`Graph::enable_profiling`]).
+    pub fn make_named_node<D, G, F, Dims>(
+        &mut self,
+        op: &'static str,
+        data: D,
+        inputs: Vec<Option<Id>>,
+        retained_bytes: usize,
+        update_func: F,
+    ) -> Value<D>
+    where
+        C::GradientAlgebra: CoreAlgebra<D, Value = G>,
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        D: HasDims<Dims = Dims>,
+        G: HasDims<Dims = Dims> + Clone + 'static,
+        Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
+        F: Fn(&mut C::GradientAlgebra, &mut C::GradientStore, G) -> Result<()>
+            + 'static
+            + Send
+            + Sync,
+    {
+        self.make_named_generic_node::<D, D, G, G, F, Dims>(
+            op,
+            data,
+            inputs,
+            retained_bytes,
+            update_func,
+        )
     }
 
     /// Create a computation node where the source type `S` may be different than the target type `D`.
+    /// * The node is recorded under the operator name `"custom"` (see
+    /// [`Graph::make_named_generic_node`]).
     pub fn make_generic_node<S, D, GS, GD, F, Dims>(
         &mut self,
         data: D,
@@ -143,48 +578,255 @@ impl<C: Config> Graph<C> {
             + Send
             + Sync,
     {
-        if inputs.iter().all(|id| id.is_none()) {
+        self.make_named_generic_node::<S, D, GS, GD, F, Dims>(
+            "custom",
+            data,
+            inputs,
+            0,
+            update_func,
+        )
+    }
+
+    /// Same as [`Graph::make_generic_node`] but records the name of the operator `op` and the
+    /// size `retained_bytes` of the values captured by `update_func` (see
+    /// [`Graph::make_named_node`]).
+    pub fn make_named_generic_node<S, D, GS, GD, F, Dims>(
+        &mut self,
+        op: &'static str,
+        data: D,
+        inputs: Vec<Option<Id>>,
+        retained_bytes: usize,
+        update_func: F,
+    ) -> Value<D>
+    where
+        C::GradientAlgebra: CoreAlgebra<S, Value = GS>,
+        C::GradientAlgebra: CoreAlgebra<D, Value = GD>,
+        C::GradientStore: GradientStore<GradientId<D>, GD>,
+        C::GradientStore: GradientStore<GradientId<S>, GS>,
+        D: HasDims<Dims = Dims>,
+        GD: HasDims<Dims = Dims> + Clone + 'static,
+        Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
+        F: Fn(&mut C::GradientAlgebra, &mut C::GradientStore, GD) -> Result<()>
+            + 'static
+            + Send
+            + Sync,
+    {
+        if !self.recording || inputs.iter().all(|id| id.is_none()) {
+            self.check_value(op, &data, None);
             return Value::constant(data);
         }
+        // A node with an input of another graph is recorded without inputs. The error is
+        // reported by the gradient computations that reach it.
+        let error = self.check_ids(op, inputs.iter().flatten()).err();
+        let info = NodeInfo::new(op, &data);
         let dims = data.dims();
-        let update_func: GradientUpdateFunc<C> =
-            Arc::new(move |algebra, store, index| -> Result<()> {
+        let profile = self.profiler.as_ref().map(|profiler| {
+            profiler.record_node(op, retained_bytes);
+            profiler.shared()
+        });
+        let update_func: Option<GradientUpdateFunc<C>> = match error {
+            Some(_) => None,
+            None => Some(Arc::new(move |algebra, store, index| -> Result<()> {
                 let value: GD = store
-                    .get(GradientId::<D>::new(index))
+                    .try_get(GradientId::<D>::new(index))?
                     .ok_or_else(|| Error::missing_gradient(func_name!()))?
                     .clone();
                 check_equal_dimensions(func_name!(), &[&value.dims(), &dims])?;
-                update_func(algebra, store, value)
-            });
+                match &profile {
+                    Some(profile) => {
+                        profile.time_backward(op, || update_func(algebra, store, value))
+                    }
+                    None => update_func(algebra, store, value),
+                }
+            })),
+        };
         let node = Node {
-            inputs,
-            update_func: Some(update_func),
+            inputs: if error.is_some() { Vec::new() } else { inputs },
+            update_func,
+            merge_func: Self::merge_gradient::<D, GD>,
+            check_func: Self::check_gradient::<D, GD>,
+            norm_func: Self::gradient_norm::<D, GD>,
+            hooks: Vec::new(),
+            error,
+            info,
         };
-        let id = Some(GradientId::new(self.nodes.alloc(node)));
+        let id = self.nodes.alloc(node);
+        self.check_value(op, &data, Some(id));
+        let id = Some(GradientId::new(id));
         Value { id, data }
     }
+
+    /// Check that the given nodes belong to this graph.
+    #[inline]
+    fn check_ids<'a, I>(&self, name: &str, ids: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a Id>,
+    {
+        let arena_id = self.nodes.next_id().arena_id();
+        for id in ids {
+            if id.arena_id() != arena_id {
+                return Err(Error::foreign_node(name, Some(*id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// With anomaly detection, remember the first non-finite forward value and attach the
+    /// error to the node `id`, if any.
+    #[inline]
+    fn check_value<D: HasDims>(&mut self, op: &'static str, data: &D, id: Option<Id>) {
+        if !self.detect_anomalies || data.all_finite() {
+            return;
+        }
+        let error = Error::anomaly(func_name!(), op, id, AnomalyKind::Value);
+        if let Some(node) = id.and_then(|id| self.nodes.get_mut(id)) {
+            node.error.get_or_insert_with(|| error.clone());
+        }
+        if self.anomaly.is_none() {
+            self.anomaly = Some(error);
+        }
+    }
+
+    /// Copy the gradient of the slot `id` found in the store `other`, if any (see
+    /// [`Graph::make_gradient_slot`]).
+    fn merge_slot<W>(
+        _graph: &mut C::GradientAlgebra,
+        store: &mut C::GradientStore,
+        other: &C::GradientStore,
+        id: Id,
+    ) -> Result<()>
+    where
+        C::GradientStore: GradientStore<GradientId<W>, W>,
+        W: Clone + 'static,
+    {
+        let gid = GradientId::<W>::new(id);
+        if let Some(value) = other.try_get(gid)? {
+            store.insert(gid, value.clone());
+        }
+        Ok(())
+    }
+
+    /// Add the gradient of the node `id` found in the store `other`, if any.
+    fn merge_gradient<D, G>(
+        graph: &mut C::GradientAlgebra,
+        store: &mut C::GradientStore,
+        other: &C::GradientStore,
+        id: Id,
+    ) -> Result<()>
+    where
+        C::GradientAlgebra: CoreAlgebra<D, Value = G>,
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        G: Clone + 'static,
+    {
+        let gid = GradientId::<D>::new(id);
+        if let Some(value) = other.try_get(gid)? {
+            store.add_gradient(graph, gid, value)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the gradient of the node `id`, if any, is finite.
+    fn check_gradient<D, G>(store: &C::GradientStore, id: Id) -> bool
+    where
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        G: HasDims,
+    {
+        !matches!(
+            store.try_get(GradientId::<D>::new(id)),
+            Ok(Some(gradient)) if !gradient.all_finite()
+        )
+    }
+
+    /// The norm of the gradient of the node `id`, if the gradient exists and the norm is
+    /// available.
+    fn gradient_norm<D, G>(store: &C::GradientStore, id: Id) -> Option<Option<f64>>
+    where
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+        G: HasDims,
+    {
+        let gradient = store.try_get(GradientId::<D>::new(id)).ok()??;
+        Some(gradient.norm())
+    }
+
+    /// With anomaly detection, check that the gradients of the nodes `targets` are finite
+    /// after running the backward computations of the node `id`.
+    #[inline]
+    fn check_gradients<'a, I>(&self, store: &C::GradientStore, id: Id, targets: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a Id>,
+    {
+        if !self.detect_anomalies {
+            return Ok(());
+        }
+        let node = self
+            .nodes
+            .get(id)
+            .ok_or_else(|| Error::missing_node(func_name!()))?;
+        for target in targets {
+            let check_func = self
+                .nodes
+                .get(*target)
+                .ok_or_else(|| Error::missing_node(func_name!()))?
+                .check_func;
+            if !check_func(store, *target) {
+                return Err(Error::anomaly(
+                    func_name!(),
+                    node.info.op,
+                    Some(id),
+                    AnomalyKind::Gradient,
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Core implementation of the automatic differentiation.
 /// We derive more precise variants below to facilitate type inference.
 impl<C: Config> Graph<C> {
+    /// Insert the initial gradients in a fresh store and return the largest seed id.
+    #[inline]
+    fn seed_gradients<D, G>(
+        graph: &mut C::GradientAlgebra,
+        store: &mut C::GradientStore,
+        seeds: Vec<(GradientId<D>, G)>,
+    ) -> Result<Option<Id>>
+    where
+        C::GradientAlgebra: CoreAlgebra<D, Value = G>,
+        C::GradientStore: GradientStore<GradientId<D>, G>,
+    {
+        let mut max_id = None;
+        for (gid, gradient) in seeds {
+            match store.try_get_mut(gid)? {
+                None => store.insert(gid, gradient),
+                Some(current) => *current = graph.add(current, &gradient)?,
+            }
+            max_id = std::cmp::max(max_id, Some(gid.inner));
+        }
+        Ok(max_id)
+    }
+
     #[inline]
     fn do_compute_gradients<D, G>(
         &self,
         graph: &mut C::GradientAlgebra,
-        gid: GradientId<D>,
-        gradient: G,
+        seeds: Vec<(GradientId<D>, G)>,
     ) -> Result<C::GradientStore>
     where
         C::GradientAlgebra: CoreAlgebra<D, Value = G>,
         C::GradientStore: GradientStore<GradientId<D>, G> + Default,
     {
+        self.check_ids(func_name!(), seeds.iter().map(|(gid, _)| &gid.inner))?;
         let mut store = C::GradientStore::default();
-        store.insert(gid, gradient);
-
         let mut heap = BinaryHeap::with_capacity(self.nodes.len());
-        heap.push(gid.inner);
-        let mut guard = gid.inner.next_id();
+        heap.extend(seeds.iter().map(|(gid, _)| gid.inner));
+        let mut guard = match Self::seed_gradients(graph, &mut store, seeds)? {
+            Some(id) => id.next_id(),
+            None => return Ok(store),
+        };
+        for id in heap.iter() {
+            self.check_gradients(&store, *id, Some(id))?;
+        }
 
         while let Some(id) = heap.pop() {
             if id < guard {
@@ -193,9 +835,8 @@ impl<C: Config> Graph<C> {
                     .nodes
                     .get(id)
                     .ok_or_else(|| Error::missing_node(func_name!()))?;
-                if let Some(update_func) = &node.update_func {
-                    update_func(graph, &mut store, id)?;
-                }
+                node.backward(graph, &mut store, id)?;
+                self.check_gradients(&store, id, node.inputs.iter().flatten())?;
                 for input in &node.inputs {
                     if let Some(id) = input {
                         heap.push(*id);
@@ -210,19 +851,23 @@ impl<C: Config> Graph<C> {
     fn do_compute_gradients_once<D, G>(
         mut self,
         graph: &mut C::GradientAlgebra,
-        gid: GradientId<D>,
-        gradient: G,
+        seeds: Vec<(GradientId<D>, G)>,
     ) -> Result<C::GradientStore>
     where
         C::GradientAlgebra: CoreAlgebra<D, Value = G>,
         C::GradientStore: GradientStore<GradientId<D>, G> + Default,
     {
+        self.check_ids(func_name!(), seeds.iter().map(|(gid, _)| &gid.inner))?;
         let mut store = C::GradientStore::default();
-        store.insert(gid, gradient);
-
         let mut heap = BinaryHeap::with_capacity(self.nodes.len());
-        heap.push(gid.inner);
-        let mut guard = gid.inner.next_id();
+        heap.extend(seeds.iter().map(|(gid, _)| gid.inner));
+        let mut guard = match Self::seed_gradients(graph, &mut store, seeds)? {
+            Some(id) => id.next_id(),
+            None => return Ok(store),
+        };
+        for id in heap.iter() {
+            self.check_gradients(&store, *id, Some(id))?;
+        }
 
         while let Some(id) = heap.pop() {
             if id < guard {
@@ -231,90 +876,554 @@ impl<C: Config> Graph<C> {
                     .nodes
                     .get_mut(id)
                     .ok_or_else(|| Error::missing_node(func_name!()))?;
-                if let Some(update_func) = &node.update_func {
-                    update_func(graph, &mut store, id)?;
-                }
-                for input in &node.inputs {
-                    if let Some(id) = input {
-                        heap.push(*id);
-                    }
-                }
+                node.backward(graph, &mut store, id)?;
+                let inputs = std::mem::take(&mut node.inputs);
                 node.clear();
+                self.check_gradients(&store, id, inputs.iter().flatten())?;
+                heap.extend(inputs.into_iter().flatten());
             }
         }
         Ok(store)
     }
+
+    /// Mark the nodes lying on a path from one of the `targets` to the node `output`, that is,
+    /// the nodes whose gradient is needed to compute the gradients of the targets.
+    fn relevant_nodes(&self, output: Id, targets: &BTreeSet<Id>) -> Vec<bool> {
+        let mut relevant = vec![false; self.nodes.len()];
+        for target in targets {
+            if let Some(flag) = relevant.get_mut(target.index()) {
+                *flag = true;
+            }
+        }
+        for (id, node) in self.nodes.iter() {
+            if id > output {
+                break;
+            }
+            let index = id.index();
+            if !relevant[index] {
+                relevant[index] = node
+                    .inputs
+                    .iter()
+                    .flatten()
+                    .any(|input| relevant[input.index()]);
+            }
+        }
+        relevant
+    }
 }
 
 /// Configuration object for first order differentials.
-pub struct Config1<E>(std::marker::PhantomData<E>);
+/// * `E` is the algebra used to compute forward values and gradients.
+/// * `S` is the gradient store (see also [`DenseGradientMap1`]).
+pub struct Config1<E, S = GenericGradientMap1>(std::marker::PhantomData<(E, S)>);
 
-impl<E: Default + Clone> Config for Config1<E> {
+impl<E: Default + Clone, S> Config for Config1<E, S> {
     type EvalAlgebra = E;
     type GradientAlgebra = E;
-    type GradientStore = GenericGradientMap1;
+    type GradientStore = S;
 }
 
 /// First order only (this is the most common case)
-impl<E: Default + Clone> Graph<Config1<E>> {
+impl<E: Default + Clone, S> Graph<Config1<E, S>> {
+    /// Propagate gradients backward, starting with the node `id`.
+    /// * Allow the graph to be re-used.
+    /// * Gradients are stored as pure data.
+    pub fn evaluate_gradients<T>(&self, id: GradientId<T>, gradient: T) -> Result<S>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        S: GradientStore1<T>,
+        T: 'static + Send + Sync,
+    {
+        let mut eval = self.eval.clone();
+        self.do_compute_gradients(&mut eval, vec![(id, gradient)])
+    }
+
     /// Propagate gradients backward, starting with the node `id`.
+    /// * Clean up memory when possible and consume the graph.
+    /// * Gradients are stored as pure data.
+    pub fn evaluate_gradients_once<T>(self, id: GradientId<T>, gradient: T) -> Result<S>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        S: GradientStore1<T>,
+        T: 'static + Send + Sync,
+    {
+        let mut eval = self.eval.clone();
+        self.do_compute_gradients_once(&mut eval, vec![(id, gradient)])
+    }
+
+    /// Propagate gradients backward, starting with all the nodes `id` of the pairs
+    /// `(id, gradient)` in `seeds` in a single pass.
+    /// * Allow the graph to be re-used.
+    /// * Gradients are stored as pure data.
+    /// * Initial gradients given for the same node are added.
+    pub fn evaluate_gradients_many<T>(&self, seeds: Vec<(GradientId<T>, T)>) -> Result<S>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        S: GradientStore1<T>,
+        T: 'static + Send + Sync,
+    {
+        let mut eval = self.eval.clone();
+        self.do_compute_gradients(&mut eval, seeds)
+    }
+
+    /// Propagate gradients backward, starting with all the nodes `id` of the pairs
+    /// `(id, gradient)` in `seeds` in a single pass.
+    /// * Clean up memory when possible and consume the graph.
+    /// * Gradients are stored as pure data.
+    /// * Initial gradients given for the same node are added.
+    pub fn evaluate_gradients_once_many<T>(self, seeds: Vec<(GradientId<T>, T)>) -> Result<S>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        S: GradientStore1<T>,
+        T: 'static + Send + Sync,
+    {
+        let mut eval = self.eval.clone();
+        self.do_compute_gradients_once(&mut eval, seeds)
+    }
+
+    /// Compute vector-Jacobian products: propagate the gradients `seeds` backward,
+    /// starting with the nodes `outputs`, and return the gradients of the nodes `inputs`.
+    /// * Allow the graph to be re-used.
+    /// * Inputs that do not receive any gradient (i.e. a zero gradient) are reported as `None`.
+    pub fn vjp<T, D>(
+        &self,
+        outputs: &[GradientId<T>],
+        seeds: Vec<T>,
+        inputs: &[GradientId<D>],
+    ) -> Result<Vec<Option<D>>>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        S: GradientStore1<T> + GradientReader<GradientId<D>, D>,
+        T: 'static + Send + Sync,
+        D: Clone + 'static + Send + Sync,
+    {
+        check_equal_lengths(func_name!(), &[outputs.len(), seeds.len()])?;
+        self.check_ids(func_name!(), inputs.iter().map(|gid| &gid.inner))?;
+        let seeds = outputs.iter().copied().zip(seeds).collect();
+        let store = self.evaluate_gradients_many(seeds)?;
+        inputs
+            .iter()
+            .map(|id| Ok(store.try_read(*id)?.cloned()))
+            .collect()
+    }
+
+    /// Compute Jacobian-vector products in reverse mode: return the differentials of the
+    /// scalar nodes `outputs` along the direction given by the `tangents` of the nodes `inputs`.
+    /// * This is a convenience on top of backward propagation: one full backward pass is run
+    /// for each output, then the gradients of the inputs are multiplied with the tangents.
+    /// * Allow the graph to be re-used.
+    /// * Inputs are either scalars or arrays (with the feature `arrayfire`) of scalars `T`.
+    /// Outputs must be scalars.
+    /// * For non-scalar outputs, or many outputs and few inputs, use forward mode instead
+    /// (see [`Forward`]) or the method `jvp` of [`GraphN`].
+    pub fn reverse_jvp<D, T>(
+        &self,
+        inputs: &[GradientId<D>],
+        tangents: Vec<D>,
+        outputs: &[GradientId<T>],
+    ) -> Result<Vec<T>>
+    where
+        E: CoreAlgebra<T, Value = T> + tangent::TangentAlgebra<D, T>,
+        S: GradientStore1<T> + GradientReader<GradientId<D>, D>,
+        T: num::Zero + num::One + 'static + Send + Sync,
+        D: 'static + Send + Sync,
+    {
+        check_equal_lengths(func_name!(), &[inputs.len(), tangents.len()])?;
+        self.check_ids(func_name!(), inputs.iter().map(|gid| &gid.inner))?;
+        let mut eval = self.eval.clone();
+        let mut results = Vec::with_capacity(outputs.len());
+        for output in outputs {
+            let store = self.do_compute_gradients(&mut eval, vec![(*output, T::one())])?;
+            let mut result = T::zero();
+            for (id, tangent) in inputs.iter().zip(&tangents) {
+                if let Some(gradient) = store.try_read(*id)? {
+                    let product = eval.tangent_dot(gradient, tangent)?;
+                    result = eval.add(&result, &product)?;
+                }
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+mod tangent {
+    use crate::{error::Result, Eval, Number};
+
+    /// Dot product of a gradient with a tangent, as needed by the method `reverse_jvp` of
+    /// [`Graph1`](crate::Graph1).
+    /// * This trait is private on purpose, so that scalars do not need to implement the
+    /// array operations of [`ArrayAlgebra`](crate::array::ArrayAlgebra).
+    pub trait TangentAlgebra<D, T> {
+        fn tangent_dot(&mut self, gradient: &D, tangent: &D) -> Result<T>;
+    }
+
+    impl<T: Number> TangentAlgebra<T, T> for Eval {
+        #[inline]
+        fn tangent_dot(&mut self, gradient: &T, tangent: &T) -> Result<T> {
+            Ok(*gradient * *tangent)
+        }
+    }
+
+    #[cfg(feature = "arrayfire")]
+    impl<T: crate::arrayfire::Float> TangentAlgebra<arrayfire::Array<T>, T> for Eval {
+        #[inline]
+        fn tangent_dot(
+            &mut self,
+            gradient: &arrayfire::Array<T>,
+            tangent: &arrayfire::Array<T>,
+        ) -> Result<T> {
+            crate::array::ArrayAlgebra::dot(self, gradient, tangent)
+        }
+    }
+}
+
+/// Operations specific to the default gradient store of first order differentials.
+impl<E: Default + Clone> Graph<Config1<E>> {
+    /// Propagate gradients backward, starting with the node `id`, and only compute the
+    /// gradients of the nodes `targets`.
     /// * Allow the graph to be re-used.
     /// * Gradients are stored as pure data.
-    pub fn evaluate_gradients<T>(
+    /// * Only the nodes lying on a path between `id` and one of the targets are visited.
+    /// Gradients of the other nodes are dropped as soon as they are no longer needed.
+    pub fn evaluate_gradients_wrt<T, D>(
         &self,
         id: GradientId<T>,
         gradient: T,
+        targets: &[GradientId<D>],
     ) -> Result<GenericGradientMap1>
     where
         E: CoreAlgebra<T, Value = T>,
-        T: 'static,
+        T: 'static + Send + Sync,
     {
+        self.check_ids(func_name!(), Some(&id.inner))?;
+        self.check_ids(func_name!(), targets.iter().map(|gid| &gid.inner))?;
+        let targets = targets.iter().map(|gid| gid.inner).collect::<BTreeSet<_>>();
+        let relevant = self.relevant_nodes(id.inner, &targets);
+        let mut store = GenericGradientMap1::default();
+        if !relevant.get(id.inner.index()).copied().unwrap_or(false) {
+            return Ok(store);
+        }
         let mut eval = self.eval.clone();
-        self.do_compute_gradients(&mut eval, id, gradient)
+        store.insert(id, gradient);
+        self.check_gradients(&store, id.inner, Some(&id.inner))?;
+
+        let mut heap = BinaryHeap::with_capacity(self.nodes.len());
+        heap.push(id.inner);
+        let mut guard = id.inner.next_id();
+
+        while let Some(id) = heap.pop() {
+            if id < guard {
+                guard = id;
+                let node = self
+                    .nodes
+                    .get(id)
+                    .ok_or_else(|| Error::missing_node(func_name!()))?;
+                node.backward(&mut eval, &mut store, id)?;
+                self.check_gradients(&store, id, node.inputs.iter().flatten())?;
+                for input in node.inputs.iter().flatten() {
+                    if relevant[input.index()] {
+                        heap.push(*input);
+                    } else {
+                        store.remove(*input);
+                    }
+                }
+                if !targets.contains(&id) {
+                    store.remove(id);
+                }
+            }
+        }
+        Ok(store)
     }
+}
 
-    /// Propagate gradients backward, starting with the node `id`.
-    /// * Clean up memory when possible and consume the graph.
+/// Multi-threaded backward propagation for first order differentials.
+#[cfg(feature = "rayon")]
+impl<E: Default + Clone + Send + Sync> Graph<Config1<E>> {
+    /// Propagate gradients backward, starting with the node `id`, using the global thread
+    /// pool of `rayon`.
+    /// * Allow the graph to be re-used.
     /// * Gradients are stored as pure data.
-    pub fn evaluate_gradients_once<T>(
-        self,
+    /// * Nodes whose gradient is fully accumulated are updated concurrently.
+    pub fn evaluate_gradients_parallel<T>(
+        &self,
         id: GradientId<T>,
         gradient: T,
     ) -> Result<GenericGradientMap1>
     where
         E: CoreAlgebra<T, Value = T>,
-        T: 'static,
+        T: 'static + Send + Sync,
     {
+        self.evaluate_gradients_many_parallel(vec![(id, gradient)])
+    }
+
+    /// Propagate gradients backward, starting with all the nodes `id` of the pairs
+    /// `(id, gradient)` in `seeds`, using the global thread pool of `rayon`.
+    /// * Allow the graph to be re-used.
+    /// * Gradients are stored as pure data.
+    /// * Initial gradients given for the same node are added.
+    pub fn evaluate_gradients_many_parallel<T>(
+        &self,
+        seeds: Vec<(GradientId<T>, T)>,
+    ) -> Result<GenericGradientMap1>
+    where
+        E: CoreAlgebra<T, Value = T>,
+        T: 'static + Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        self.check_ids(func_name!(), seeds.iter().map(|(gid, _)| &gid.inner))?;
         let mut eval = self.eval.clone();
-        self.do_compute_gradients_once(&mut eval, id, gradient)
+        let mut store = GenericGradientMap1::default();
+        let mut heap = BinaryHeap::with_capacity(self.nodes.len());
+        heap.extend(seeds.iter().map(|(gid, _)| gid.inner));
+        let mut guard = match Self::seed_gradients(&mut eval, &mut store, seeds)? {
+            Some(id) => id.next_id(),
+            None => return Ok(store),
+        };
+        for id in heap.iter() {
+            self.check_gradients(&store, *id, Some(id))?;
+        }
+
+        // Count the number of pending updates for each node reachable from the seeds.
+        let mut pending = vec![0usize; self.nodes.len()];
+        let mut reachable = Vec::new();
+        while let Some(id) = heap.pop() {
+            if id < guard {
+                guard = id;
+                let node = self
+                    .nodes
+                    .get(id)
+                    .ok_or_else(|| Error::missing_node(func_name!()))?;
+                for input in node.inputs.iter().flatten() {
+                    pending[input.index()] += 1;
+                    heap.push(*input);
+                }
+                reachable.push(id);
+            }
+        }
+
+        // Process nodes by waves. Each wave contains the nodes whose gradient is final. Updates
+        // run concurrently on separate stores, then the gradients of the inputs are added to the
+        // main store.
+        let mut ready = reachable
+            .into_iter()
+            .filter(|id| pending[id.index()] == 0)
+            .collect::<Vec<_>>();
+        while !ready.is_empty() {
+            let mut wave = Vec::with_capacity(ready.len());
+            for id in ready {
+                let node = self
+                    .nodes
+                    .get(id)
+                    .ok_or_else(|| Error::missing_node(func_name!()))?;
+                wave.push((id, node));
+            }
+            let tasks = wave
+                .iter()
+                .filter(|(_, node)| node.has_backward())
+                .copied()
+                .collect::<Vec<_>>();
+            if let [(id, node)] = tasks.as_slice() {
+                node.backward(&mut eval, &mut store, *id)?;
+                self.check_gradients(&store, *id, node.inputs.iter().flatten())?;
+            } else if !tasks.is_empty() {
+                let tasks = tasks
+                    .into_iter()
+                    .map(|(id, node)| (id, node, store.extract(std::iter::once(id))))
+                    .collect::<Vec<_>>();
+                let results = tasks
+                    .into_par_iter()
+                    .map(|(id, node, mut local)| {
+                        let mut eval = eval.clone();
+                        node.backward(&mut eval, &mut local, id)?;
+                        Ok((id, node, local))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                for (id, node, mut local) in results {
+                    store.append(local.extract(std::iter::once(id)));
+                    let mut inputs = node.inputs.iter().flatten().copied().collect::<Vec<_>>();
+                    inputs.sort();
+                    inputs.dedup();
+                    for input in inputs {
+                        let merge_func = self
+                            .nodes
+                            .get(input)
+                            .ok_or_else(|| Error::missing_node(func_name!()))?
+                            .merge_func;
+                        merge_func(&mut eval, &mut store, &local, input)?;
+                    }
+                    self.check_gradients(&store, id, node.inputs.iter().flatten())?;
+                }
+            }
+            ready = Vec::new();
+            for (_, node) in wave {
+                for input in node.inputs.iter().flatten() {
+                    let count = &mut pending[input.index()];
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(*input);
+                    }
+                }
+            }
+        }
+        Ok(store)
     }
 }
 
 /// Configuration object for higher-order differentials.
-pub struct ConfigN<E>(std::marker::PhantomData<E>);
+/// * `E` is the algebra used to compute forward values.
+/// * `S` is the gradient store (see also [`DenseGradientMapN`]).
+pub struct ConfigN<E, S = GenericGradientMapN>(std::marker::PhantomData<(E, S)>);
 
-impl<E: Default + Clone> Config for ConfigN<E> {
+impl<E: Default + Clone, S> Config for ConfigN<E, S> {
     type EvalAlgebra = E;
-    type GradientAlgebra = Graph<ConfigN<E>>;
-    type GradientStore = GenericGradientMapN;
+    type GradientAlgebra = Graph<ConfigN<E, S>>;
+    type GradientStore = S;
 }
 
 /// Higher order differentials.
-impl<E: Default + Clone> Graph<ConfigN<E>> {
+impl<E: Default + Clone, S> Graph<ConfigN<E, S>> {
     /// Propagate gradients backward, starting with the node `id`.
     /// * Gradients are computed as graph values that can be differentiated later.
     /// * The graph is augmented with the nodes corresponding to gradient computations.
-    pub fn compute_gradients<D>(
-        &mut self,
-        id: GradientId<D>,
-        gradient: Value<D>,
-    ) -> Result<GenericGradientMapN>
+    pub fn compute_gradients<D>(&mut self, id: GradientId<D>, gradient: Value<D>) -> Result<S>
     where
         Self: CoreAlgebra<D, Value = Value<D>>,
-        D: 'static,
+        S: GradientStoreN<D>,
+        D: 'static + Send + Sync,
+    {
+        self.compute_gradients_many(vec![(id, gradient)])
+    }
+
+    /// Propagate gradients backward, starting with all the nodes `id` of the pairs
+    /// `(id, gradient)` in `seeds` in a single pass.
+    /// * Gradients are computed as graph values that can be differentiated later.
+    /// * The graph is augmented with the nodes corresponding to gradient computations.
+    /// * Initial gradients given for the same node are added.
+    pub fn compute_gradients_many<D>(&mut self, seeds: Vec<(GradientId<D>, Value<D>)>) -> Result<S>
+    where
+        Self: CoreAlgebra<D, Value = Value<D>>,
+        S: GradientStoreN<D>,
+        D: 'static + Send + Sync,
     {
         let current = self.clone();
-        current.do_compute_gradients_once(self, id, gradient)
+        current.do_compute_gradients_once(self, seeds)
+    }
+
+    /// Compute vector-Jacobian products: propagate the gradients `seeds` backward,
+    /// starting with the nodes `outputs`, and return the gradients of the nodes `inputs`.
+    /// * Gradients are computed as graph values that can be differentiated later.
+    /// * Inputs that do not receive any gradient (i.e. a zero gradient) are reported as `None`.
+    pub fn vjp<T, D>(
+        &mut self,
+        outputs: &[GradientId<T>],
+        seeds: Vec<Value<T>>,
+        inputs: &[GradientId<D>],
+    ) -> Result<Vec<Option<Value<D>>>>
+    where
+        Self: CoreAlgebra<T, Value = Value<T>>,
+        S: GradientStoreN<T> + GradientReader<GradientId<D>, Value<D>>,
+        T: 'static + Send + Sync,
+        D: Clone + 'static + Send + Sync,
+    {
+        check_equal_lengths(func_name!(), &[outputs.len(), seeds.len()])?;
+        self.check_ids(func_name!(), inputs.iter().map(|gid| &gid.inner))?;
+        let seeds = outputs.iter().copied().zip(seeds).collect();
+        let store = self.compute_gradients_many(seeds)?;
+        inputs
+            .iter()
+            .map(|id| Ok(store.try_read(*id)?.cloned()))
+            .collect()
+    }
+
+    /// Compute Jacobian-vector products: return the differentials of the nodes `outputs`
+    /// along the direction given by the `tangents` of the nodes `inputs`.
+    /// * Differentials are computed as graph values that can be differentiated later.
+    /// * Outputs whose differential is zero are reported as `None`.
+    ///
+    /// This uses two backward passes: the first one computes the gradients of the inputs
+    /// for placeholder output gradients `u` (aka. `J^T u`); the second one differentiates
+    /// the result along `tangents` with respect to `u`.
+    pub fn jvp<D, T>(
+        &mut self,
+        inputs: &[GradientId<D>],
+        tangents: Vec<Value<D>>,
+        outputs: &[Value<T>],
+    ) -> Result<Vec<Option<Value<T>>>>
+    where
+        Self: CoreAlgebra<D, Value = Value<D>> + CoreAlgebra<T, Value = Value<T>>,
+        E: ArithAlgebra<T>,
+        S: GradientStoreN<D> + GradientStoreN<T>,
+        T: Clone + 'static + Send + Sync,
+        D: Clone + 'static + Send + Sync,
+    {
+        check_equal_lengths(func_name!(), &[inputs.len(), tangents.len()])?;
+        self.check_ids(func_name!(), inputs.iter().map(|gid| &gid.inner))?;
+        let mut placeholders = Vec::with_capacity(outputs.len());
+        let mut seeds = Vec::with_capacity(outputs.len());
+        for output in outputs {
+            match output.id() {
+                Some(id) => {
+                    let zeros = self.eval().zeros(output.data());
+                    let u = self.variable(zeros);
+                    placeholders.push(u.id());
+                    seeds.push((id, u));
+                }
+                None => placeholders.push(None),
+            }
+        }
+        let gradients = self.compute_gradients_many(seeds)?;
+
+        let mut seeds = Vec::with_capacity(inputs.len());
+        for (id, tangent) in inputs.iter().zip(tangents) {
+            if let Some(gid) = gradients.try_get(*id)?.and_then(Value::id) {
+                seeds.push((gid, tangent));
+            }
+        }
+        let store = self.compute_gradients_many(seeds)?;
+        Ok(placeholders
+            .into_iter()
+            .map(|u| u.and_then(|id| store.get(id).cloned()))
+            .collect())
+    }
+
+    /// Compute the product of the Hessian matrix of the scalar node `id` with the vector given
+    /// by the values `vector` of the nodes `inputs`, without materializing the Hessian.
+    /// * Products are computed as graph values that can be differentiated later.
+    /// * Inputs whose product is zero are reported as `None`.
+    ///
+    /// The first backward pass computes the gradients `g` of the inputs as nodes of the graph.
+    /// The second one differentiates the dot product `<g, vector>` by seeding each gradient
+    /// node with the corresponding element of `vector`.
+    pub fn hessian_vector_product<T, D>(
+        &mut self,
+        id: GradientId<T>,
+        inputs: &[GradientId<D>],
+        vector: Vec<Value<D>>,
+    ) -> Result<Vec<Option<Value<D>>>>
+    where
+        Self: CoreAlgebra<T, Value = Value<T>> + CoreAlgebra<D, Value = Value<D>>,
+        S: GradientStoreN<T> + GradientStoreN<D>,
+        T: num::One + 'static + Send + Sync,
+        D: Clone + 'static + Send + Sync,
+    {
+        check_equal_lengths(func_name!(), &[inputs.len(), vector.len()])?;
+        self.check_ids(func_name!(), inputs.iter().map(|gid| &gid.inner))?;
+        let one = self.constant(T::one());
+        let gradients = self.compute_gradients(id, one)?;
+
+        let mut seeds = Vec::with_capacity(inputs.len());
+        for (id, v) in inputs.iter().zip(vector) {
+            if let Some(gid) = gradients.try_get(*id)?.and_then(Value::id) {
+                seeds.push((gid, v));
+            }
+        }
+        let store = self.compute_gradients_many(seeds)?;
+        inputs
+            .iter()
+            .map(|id| Ok(store.try_read(*id)?.cloned()))
+            .collect()
     }
 }
 
@@ -346,6 +1455,12 @@ impl<C: Config> Clone for Node<C> {
         Self {
             inputs: self.inputs.clone(),
             update_func: self.update_func.clone(),
+            merge_func: self.merge_func,
+            check_func: self.check_func,
+            norm_func: self.norm_func,
+            hooks: self.hooks.clone(),
+            error: self.error.clone(),
+            info: self.info.clone(),
         }
     }
 }
@@ -355,6 +1470,10 @@ impl<C: Config> Clone for Graph<C> {
         Self {
             nodes: self.nodes.clone(),
             eval: self.eval.clone(),
+            recording: self.recording,
+            profiler: self.profiler.clone(),
+            detect_anomalies: self.detect_anomalies,
+            anomaly: self.anomaly.clone(),
         }
     }
 }
@@ -363,14 +1482,25 @@ impl<C: Config> std::fmt::Debug for Node<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         f.debug_struct("Node")
             .field("inputs", &self.inputs)
+            .field("info", &self.info)
             .finish()
     }
 }
 
+impl std::fmt::Debug for NodeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{} {:?}", self.op, self.dims)?;
+        if let Some(label) = &self.label {
+            write!(f, " {:?}", label)?;
+        }
+        Ok(())
+    }
+}
+
 impl<C: Config> std::fmt::Debug for Graph<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         for (id, node) in self.nodes.iter() {
-            write!(f, "{:?} <- {:?}; ", id, node.inputs)?;
+            write!(f, "{:?} <- {:?} {:?}; ", id, node.inputs, node.info)?;
         }
         Ok(())
     }
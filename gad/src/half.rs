@@ -0,0 +1,201 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// The types `half::f16` and `half::bf16` do not provide arithmetic operations, hence the
+// wrappers below. Operations are computed in `f32` then rounded to the reduced precision.
+
+use crate::{analytic::AnalyticAlgebra, error::Result, net::CastWeights, Eval, Number};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Rem, Sub};
+
+macro_rules! impl_binary_op {
+    ($name:ident, $trait:ident, $method:ident, $op:tt) => {
+        impl $trait for $name {
+            type Output = Self;
+
+            #[inline]
+            fn $method(self, other: Self) -> Self {
+                Self::from(f32::from(self) $op f32::from(other))
+            }
+        }
+    };
+}
+
+macro_rules! impl_unary_func {
+    ($name:ident, $method:ident, $f:expr) => {
+        #[inline]
+        fn $method(&mut self, v: &$name) -> $name {
+            let f: fn(f32) -> f32 = $f;
+            $name::from(f(f32::from(*v)))
+        }
+    };
+}
+
+macro_rules! impl_half {
+    ($name:ident, $half:ty, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub $half);
+
+        impl From<$half> for $name {
+            #[inline]
+            fn from(x: $half) -> Self {
+                $name(x)
+            }
+        }
+
+        impl From<$name> for $half {
+            #[inline]
+            fn from(x: $name) -> Self {
+                x.0
+            }
+        }
+
+        impl From<f32> for $name {
+            #[inline]
+            fn from(x: f32) -> Self {
+                $name(<$half>::from_f32(x))
+            }
+        }
+
+        impl From<$name> for f32 {
+            #[inline]
+            fn from(x: $name) -> Self {
+                x.0.to_f32()
+            }
+        }
+
+        impl From<i16> for $name {
+            #[inline]
+            fn from(c: i16) -> Self {
+                Self::from(f32::from(c))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl_binary_op!($name, Add, add, +);
+        impl_binary_op!($name, Sub, sub, -);
+        impl_binary_op!($name, Mul, mul, *);
+        impl_binary_op!($name, Div, div, /);
+        impl_binary_op!($name, Rem, rem, %);
+
+        impl Neg for $name {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                Self::from(-f32::from(self))
+            }
+        }
+
+        impl AddAssign for $name {
+            #[inline]
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl num::Zero for $name {
+            #[inline]
+            fn zero() -> Self {
+                Self::from(0.0)
+            }
+
+            #[inline]
+            fn is_zero(&self) -> bool {
+                f32::from(*self) == 0.0
+            }
+        }
+
+        impl num::One for $name {
+            #[inline]
+            fn one() -> Self {
+                Self::from(1.0)
+            }
+        }
+
+        impl num::Num for $name {
+            type FromStrRadixErr = <f32 as num::Num>::FromStrRadixErr;
+
+            fn from_str_radix(
+                s: &str,
+                radix: u32,
+            ) -> std::result::Result<Self, Self::FromStrRadixErr> {
+                <f32 as num::Num>::from_str_radix(s, radix).map(Self::from)
+            }
+        }
+
+        impl num::pow::Pow<i16> for $name {
+            type Output = Self;
+
+            #[inline]
+            fn pow(self, c: i16) -> Self {
+                Self::from(f32::from(self).powi(c.into()))
+            }
+        }
+
+        impl Number for $name {
+            #[inline]
+            fn is_finite_number(&self) -> bool {
+                self.0.is_finite()
+            }
+
+            #[inline]
+            fn magnitude(&self) -> Option<f64> {
+                Some(f64::from(self.0.to_f32()).abs())
+            }
+        }
+
+        impl CastWeights<f32> for $name {
+            #[inline]
+            fn cast_from(weights: &f32) -> Self {
+                Self::from(*weights)
+            }
+
+            #[inline]
+            fn cast_into(&self) -> f32 {
+                f32::from(*self)
+            }
+        }
+
+        impl AnalyticAlgebra<$name> for Eval {
+            impl_unary_func!($name, exp, f32::exp);
+            impl_unary_func!($name, log, f32::ln);
+            impl_unary_func!($name, log1p, f32::ln_1p);
+            impl_unary_func!($name, sin, f32::sin);
+            impl_unary_func!($name, cos, f32::cos);
+            impl_unary_func!($name, tanh, f32::tanh);
+            impl_unary_func!($name, sigmoid, |x| 1.0 / (1.0 + f32::exp(-x)));
+            impl_unary_func!($name, reciprocal, f32::recip);
+            impl_unary_func!($name, sqrt, f32::sqrt);
+
+            #[inline]
+            fn div(&mut self, v0: &$name, v1: &$name) -> Result<$name> {
+                Ok(*v0 / *v1)
+            }
+
+            #[inline]
+            fn pow(&mut self, v0: &$name, v1: &$name) -> Result<$name> {
+                Ok($name::from(f32::from(*v0).powf(f32::from(*v1))))
+            }
+        }
+    };
+}
+
+impl_half!(
+    F16,
+    ::half::f16,
+    "A 16-bit floating point number (IEEE 754 binary16) wrapping `half::f16`."
+);
+
+impl_half!(
+    Bf16,
+    ::half::bf16,
+    "A 16-bit \"brain\" floating point number (bfloat16) wrapping `half::bf16`."
+);
@@ -0,0 +1,201 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    core::CoreAlgebra,
+    error::{check_equal_lengths, Result},
+    forward::Dual,
+    graph::Value,
+    net::HasGradientId,
+    store::GradientStore,
+    Forward1, Graph1, GraphN, Number,
+};
+
+#[cfg(feature = "arrayfire")]
+use crate::arrayfire::Float;
+#[cfg(feature = "arrayfire")]
+use arrayfire as af;
+
+/// Compute the Jacobian matrix of `f` at the point `inputs` using backward propagation.
+/// * The closure `f` is typically a generic formula instantiated with the algebra `Graph1`.
+/// * The result is indexed as `jacobian[output][input]`.
+/// * One backward pass is run for each output.
+pub fn jacobian<T, F>(f: F, inputs: &[T]) -> Result<Vec<Vec<T>>>
+where
+    F: FnOnce(&mut Graph1, &[Value<T>]) -> Result<Vec<Value<T>>>,
+    T: Number,
+{
+    let mut g = Graph1::new();
+    let inputs = inputs.iter().map(|x| g.variable(*x)).collect::<Vec<_>>();
+    let outputs = f(&mut g, &inputs)?;
+    let ids = inputs.iter().map(Value::gid).collect::<Result<Vec<_>>>()?;
+    let mut rows = Vec::with_capacity(outputs.len());
+    for output in &outputs {
+        let row = match output.id() {
+            Some(id) => g
+                .vjp(&[id], vec![T::one()], &ids)?
+                .into_iter()
+                .map(|x| x.unwrap_or_else(T::zero))
+                .collect(),
+            None => vec![T::zero(); ids.len()],
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Compute the Jacobian matrix of `f` at the point `inputs` in forward mode.
+/// * The closure `f` is typically a generic formula instantiated with the algebra `Forward1`.
+/// * The result is indexed as `jacobian[output][input]`.
+/// * One forward pass is run for each input.
+pub fn jacobian_forward<T, F>(f: F, inputs: &[T]) -> Result<Vec<Vec<T>>>
+where
+    F: Fn(&mut Forward1, &[Dual<T>]) -> Result<Vec<Dual<T>>>,
+    T: Number,
+{
+    let mut g = Forward1::new();
+    if inputs.is_empty() {
+        let outputs = f(&mut g, &[])?;
+        return Ok(vec![Vec::new(); outputs.len()]);
+    }
+    let mut columns = Vec::with_capacity(inputs.len());
+    for j in 0..inputs.len() {
+        let mut duals = Vec::with_capacity(inputs.len());
+        for (i, x) in inputs.iter().enumerate() {
+            if i == j {
+                duals.push(g.dual(*x, T::one())?);
+            } else {
+                duals.push(g.constant(*x));
+            }
+        }
+        let outputs = f(&mut g, &duals)?;
+        let column = outputs
+            .iter()
+            .map(|y| y.tangent().cloned().unwrap_or_else(T::zero))
+            .collect::<Vec<_>>();
+        columns.push(column);
+    }
+    check_equal_lengths(
+        func_name!(),
+        &columns.iter().map(Vec::len).collect::<Vec<_>>(),
+    )?;
+    Ok((0..columns[0].len())
+        .map(|i| columns.iter().map(|column| column[i]).collect())
+        .collect())
+}
+
+/// Compute the Hessian matrix of the scalar function `f` at the point `inputs`.
+/// * The closure `f` is typically a generic formula instantiated with the algebra `GraphN`.
+/// * The result is indexed as `hessian[input][input]`.
+/// * One backward pass is run to obtain the gradient, then one for each input.
+/// * The nodes of the gradient are recorded once. The passes of the rows do not record new
+/// nodes, hence each of them costs a traversal of the same graph.
+pub fn hessian<T, F>(f: F, inputs: &[T]) -> Result<Vec<Vec<T>>>
+where
+    F: FnOnce(&mut GraphN, &[Value<T>]) -> Result<Value<T>>,
+    T: Number,
+{
+    let mut g = GraphN::new();
+    let inputs = inputs.iter().map(|x| g.variable(*x)).collect::<Vec<_>>();
+    let output = f(&mut g, &inputs)?;
+    let ids = inputs.iter().map(Value::gid).collect::<Result<Vec<_>>>()?;
+    let id = match output.id() {
+        Some(id) => id,
+        None => return Ok(vec![vec![T::zero(); ids.len()]; ids.len()]),
+    };
+    let one = g.constant(T::one());
+    let gradients = g.compute_gradients(id, one)?;
+    g.no_grad(|g| {
+        let mut rows = Vec::with_capacity(ids.len());
+        for x in &ids {
+            let row = match gradients.get(*x).and_then(Value::id) {
+                Some(id) => {
+                    let one = g.constant(T::one());
+                    g.vjp(&[id], vec![one], &ids)?
+                        .into_iter()
+                        .map(|x| x.map_or_else(T::zero, |x| *x.data()))
+                        .collect()
+                }
+                None => vec![T::zero(); ids.len()],
+            };
+            rows.push(row);
+        }
+        Ok(rows)
+    })
+}
+
+/// Unit array of the given dimensions with a one at the flat index `k`.
+#[cfg(feature = "arrayfire")]
+fn unit_array<T: Float>(dims: af::Dim4, k: u64) -> af::Array<T> {
+    let mut values = vec![T::zero(); dims.elements() as usize];
+    values[k as usize] = T::one();
+    af::Array::new(&values, dims)
+}
+
+/// Compute the Jacobian matrix of `f` at the point `input` using backward propagation.
+/// * The result is an array of dimensions `(m, n)` where `m` and `n` are the number of
+/// elements of the output and the input respectively. Elements are numbered in
+/// column-major order, as in `af::flat`.
+/// * One backward pass is run for each element of the output.
+#[cfg(feature = "arrayfire")]
+pub fn array_jacobian<T, F>(f: F, input: af::Array<T>) -> Result<af::Array<T>>
+where
+    F: FnOnce(&mut Graph1, &Value<af::Array<T>>) -> Result<Value<af::Array<T>>>,
+    T: Float,
+{
+    let mut g = Graph1::new();
+    let n = input.elements() as u64;
+    let input = g.variable(input);
+    let output = f(&mut g, &input)?;
+    let m = output.data().elements() as u64;
+    let mut result = af::constant(T::zero(), af::dim4!(m, n));
+    if let Some(id) = output.id() {
+        for k in 0..m {
+            let seed = unit_array(output.data().dims(), k);
+            if let Some(gradient) = g.vjp(&[id], vec![seed], &[input.gid()?])?.pop().flatten() {
+                let row = af::transpose(&af::flat(&gradient), false);
+                af::set_row(&mut result, &row, k as i64);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Compute the Hessian matrix of the scalar function `f` at the point `input`.
+/// * The result is an array of dimensions `(n, n)` where `n` is the number of elements of
+/// the input. Elements are numbered in column-major order, as in `af::flat`.
+/// * One backward pass is run to obtain the gradient, then one for each element of the input.
+/// As in [`hessian`], only the nodes of the gradient are recorded.
+#[cfg(feature = "arrayfire")]
+pub fn array_hessian<T, F>(f: F, input: af::Array<T>) -> Result<af::Array<T>>
+where
+    F: FnOnce(&mut GraphN, &Value<af::Array<T>>) -> Result<Value<T>>,
+    T: Float,
+{
+    let mut g = GraphN::new();
+    let n = input.elements() as u64;
+    let dims = input.dims();
+    let input = g.variable(input);
+    let output = f(&mut g, &input)?;
+    let mut result = af::constant(T::zero(), af::dim4!(n, n));
+    let id = match output.id() {
+        Some(id) => id,
+        None => return Ok(result),
+    };
+    let one = g.constant(T::one());
+    let gradients = g.compute_gradients(id, one)?;
+    if let Some(id) = gradients.get(input.gid()?).and_then(Value::id) {
+        let x = input.gid()?;
+        g.no_grad(|g| -> Result<()> {
+            for k in 0..n {
+                let seed = g.constant(unit_array(dims, k));
+                if let Some(gradient) = g.vjp(&[id], vec![seed], &[x])?.pop().flatten() {
+                    let row = af::transpose(&af::flat(gradient.data()), false);
+                    af::set_row(&mut result, &row, k as i64);
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
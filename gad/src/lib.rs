@@ -11,6 +11,7 @@
 //! The following modes of execution are currently supported for all library-defined operators:
 //! * first-order differentiation,
 //! * higher-order differentiation,
+//! * first-order differentiation in forward mode,
 //! * forward-only evaluation, and
 //! * dimension checking.
 //!
@@ -31,6 +32,8 @@
 //! sum of two arrays `x` and `y` may be written `g.add(&x, &y)?`.
 //!
 //! * All structures and values implement `Send` and `Sync` to support concurrent programming.
+//! With the optional feature `rayon`, the backward propagation of `Graph1` may also run
+//! independent branches of the tape on a thread pool (see `evaluate_gradients_parallel`).
 //!
 //! * Generic programming is encouraged so that user formulas can be interpreted in
 //! different modes of execution (forward evaluation, dimension checking, etc) with
@@ -43,18 +46,56 @@
 //!
 //! ## Limitations
 //!
-//! * Currently, the usual syntax of operators `+`, `-`, `*`, etc is not available for
-//! differentiable values. All operations are method calls of the form `g.op(x1, .. xN)`
-//! (or typically `g.op(x1, .. xN)?` for fallible operations).
+//! * The usual syntax of operators `+`, `-`, `*`, etc is not available for the
+//! differentiable values of the main algebras. All operations are method calls of the form
+//! `g.op(x1, .. xN)` (or typically `g.op(x1, .. xN)?` for fallible operations).
 //!
 //! * Because of a current [limitation](https://github.com/rust-lang/rust/issues/49434) of
 //! the Rust borrow checker, expressions cannot be nested: `g.add(&x, &g.mul(&y, &z)?)?` must
 //! be written `let v = g.mul(&y, &z)?; g.add(&x, &v)?`.
 //!
-//! We believe that this state of affairs could be improved in the future using Rust
-//! macros. Alternatively, future extensions of the library could define a new category of
-//! differentiable values that contain an implicit `RefCell` reference to a common tape
-//! and provide (implicitly fallible, thread unsafe) operator traits for these values.
+//! With the optional feature `macros`, the macro `gad_expr!` rewrites nested formulas into
+//! the required sequence of operations, for any algebra:
+//! ```
+//! # #[cfg(feature = "macros")]
+//! # fn main() -> gad::error::Result<()> {
+//! use gad::prelude::*;
+//!
+//! fn formula<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+//! where
+//!     A: CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V> + ConstArithAlgebra<V, f32>,
+//! {
+//!     // Fallible operations are followed by `?`.
+//!     Ok(gad_expr!(g, x * sin(y) + exp(x / y) - 1.0f32))
+//! }
+//!
+//! let mut g = Graph1::new();
+//! let x = g.variable(0.5f32);
+//! let y = g.variable(2f32);
+//! let z = formula(&mut g, &x, &y)?;
+//! # assert!((*z.data() - formula(&mut Eval::default(), &0.5f32, &2f32)?).abs() < 1e-6);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # fn main() {}
+//! ```
+//!
+//! Alternatively, the module `tape` defines a new category of differentiable
+//! values that contain an implicit `RefCell` reference to a common tape and provide
+//! (implicitly fallible, thread unsafe) operator traits for these values:
+//! ```
+//! use gad::{prelude::*, tape::Tape};
+//! # fn main() -> Result<()> {
+//! let tape = Tape::new(Graph1::new());
+//! let x = tape.variable(0.5f32);
+//! let y = tape.variable(2f32);
+//! // Errors, if any, are reported when the final value is extracted.
+//! let z = ((&x * &y + x.sin()) / &y).into_value()?;
+//! let gradients = tape.borrow_mut().evaluate_gradients(z.gid()?, 1.0)?;
+//! # assert!((gradients.get(y.value()?.gid()?).unwrap() + 0.5f32.sin() / 4.0).abs() < 1e-6);
+//! # Ok(())
+//! # }
+//! ```
 //!
 //! ## Quick Start
 //!
@@ -216,6 +257,49 @@
 //! # }
 //! ```
 //!
+//! Dense Jacobian and Hessian matrices of generic formulas may also be obtained directly
+//! with the helper functions of the module `jacobian`.
+//!
+//! ```
+//! # use gad::prelude::*;
+//! # fn main() -> Result<()> {
+//! // z = x * y^2
+//! let hessian = gad::jacobian::hessian(
+//!     |g, v| {
+//!         let h = g.mul(&v[0], &v[1])?;
+//!         g.mul(&h, &v[1])
+//!     },
+//!     &[1.0f32, 0.4],
+//! )?;
+//! assert_eq!(hessian, vec![vec![0.0, 0.8], vec![0.8, 2.0]]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Forward-Mode Differentials
+//!
+//! For functions with few inputs and many outputs, the algebra `Forward1` computes
+//! first-order differentials in forward mode (aka. "tangents") without recording a tape.
+//! Values are dual numbers carrying a tangent alongside their data.
+//!
+//! ```
+//! # use gad::prelude::*;
+//! # fn main() -> Result<()> {
+//! let mut g = Forward1::new();
+//! // Differentiate along the direction of x.
+//! let x = g.dual(1.0f32, 1.0)?;
+//! let y = g.constant(0.4f32);
+//! // z = x^2 * y
+//! let z = {
+//!     let h = g.mul(&x, &x)?;
+//!     g.mul(&h, &y)?
+//! };
+//! assert_eq!(*z.data(), 0.4);
+//! assert_eq!(*z.tangent().unwrap(), 0.8); // 2xy
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Extending Automatic Differentiation
 //!
 //! ### Operations and algebras
@@ -449,23 +533,30 @@ pub mod prelude {
         compare::CompareAlgebra,
         const_arith::ConstArithAlgebra,
         core::{CoreAlgebra, HasDims},
-        error::{check_equal_dimensions, Error, Result},
+        error::{check_equal_dimensions, AnomalyKind, Error, Result},
+        forward::{Dual, Forward},
         func_name,
         graph::{Config1, ConfigN, Graph, Value},
         linked::LinkedAlgebra,
         matrix::{MatProp, MatrixAlgebra},
         net::{
-            CheckNet as _, ConstantData, EvalNet as _, HasGradientId, HasGradientReader, InputData,
-            Net, WeightData, WeightOps,
+            CastWeights, CheckNet as _, ConstantData, EvalNet as _, HasGradientId,
+            HasGradientReader, InputData, Net, WeightData, WeightOps,
         },
-        net_ext::{DiffNet as _, SingleOutputNet as _},
-        store::{GradientId, GradientReader, GradientStore},
-        Check, Eval, Graph1, GraphN, Number,
+        net_ext::{DiffNet as _, SingleOutputNet as _, TracedNet as _},
+        store::{GradientId, GradientReader, GradientStore, GradientStore1, GradientStoreN},
+        Check, Eval, Forward1, Graph1, GraphN, Number,
     };
     pub use thiserror::Error as _;
 
     #[cfg(feature = "arrayfire")]
     pub use crate::arrayfire::{testing, AfAlgebra, Float, FullAlgebra};
+
+    #[cfg(feature = "half")]
+    pub use crate::half::{Bf16, F16};
+
+    #[cfg(feature = "macros")]
+    pub use crate::{gad_expr, Net};
 }
 
 /// Error and result types.
@@ -475,9 +566,24 @@ pub mod error;
 /// Provide algebras supporting higher-order, tape-based auto-differentiation.
 pub mod graph;
 
+/// Opt-in statistics on the operators recorded by a graph.
+pub mod profile;
+
 /// Core operations.
 pub mod core;
 
+/// Provide an algebra supporting first-order auto-differentiation in forward mode.
+pub mod forward;
+
+/// Dense Jacobian and Hessian matrices.
+pub mod jacobian;
+
+/// Values with an implicit shared tape and the usual operators `+`, `-`, `*`, `/`.
+pub mod tape;
+
+/// Record the operations of a generic formula once, then replay them on new inputs.
+pub mod trace;
+
 /// Pointwise analytic functions (cos, sin, log, exp, pow, sqrt, ..)
 pub mod analytic;
 
@@ -511,10 +617,27 @@ pub mod net;
 /// Network extensions.
 pub mod net_ext;
 
+/// Rewrite nested formulas into calls to the operations of an algebra (feature `macros`).
+#[cfg(feature = "macros")]
+pub use gad_macros::gad_expr;
+
+/// Derive the trait `net::Net` for a struct of sub-networks (feature `macros`).
+#[cfg(feature = "macros")]
+pub use gad_macros::Net;
+
+// Used by the code generated by `#[derive(Net)]`.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub use serde;
+
 /// Additional definitions for Arrayfire.
 #[cfg(feature = "arrayfire")]
 pub mod arrayfire;
 
+/// Reduced-precision numbers `F16` and `Bf16` based on the crate `half` (feature `half`).
+#[cfg(feature = "half")]
+pub mod half;
+
 /// The default algebra that only checks dimensions.
 #[derive(Clone, Default)]
 pub struct Check;
@@ -531,6 +654,9 @@ pub type Graph1 = graph::Graph<graph::Config1<Eval>>;
 /// The default algebra that allows computing higher-order differentials.
 pub type GraphN = graph::Graph<graph::ConfigN<Eval>>;
 
+/// The default algebra that allows computing first-order differentials in forward mode.
+pub type Forward1 = forward::Forward<Eval>;
+
 impl Eval {
     /// Access the underlying default "Check" algebra.
     #[inline]
@@ -539,26 +665,14 @@ impl Eval {
     }
 }
 
-mod private {
-    /// "Sealed" trait used to avoid conflicting trait implementations.
-    pub trait Reserved {}
-
-    impl Reserved for i8 {}
-    impl Reserved for i16 {}
-    impl Reserved for i32 {}
-    impl Reserved for i64 {}
-    impl Reserved for f32 {}
-    impl Reserved for f64 {}
-    impl Reserved for num::complex::Complex<f32> {}
-    impl Reserved for num::complex::Complex<f64> {}
-    impl Reserved for num::Rational32 {}
-    impl Reserved for num::Rational64 {}
-}
-
 /// Supported numbers for default algebras.
+/// * Other crates may implement this trait for their own scalar types (e.g. fixed-point
+/// numbers) to use them with [`Eval`], [`Check`], [`Graph1`], and [`GraphN`]. Analytic
+/// functions additionally require `num::Float`, and comparisons `PartialOrd`.
+/// * Numbers have no dimensions: every implementation of `Number` also implements
+/// [`HasDims`](core::HasDims) with `Dims = ()`, so that array types cannot implement this trait.
 pub trait Number:
-    private::Reserved
-    + num::Num
+    num::Num
     + std::ops::Neg<Output = Self>
     + std::ops::AddAssign
     + std::fmt::Debug
@@ -570,15 +684,54 @@ pub trait Number:
     + Send
     + Sync
 {
+    /// Whether the number is finite, that is, neither NaN nor infinite.
+    #[inline]
+    fn is_finite_number(&self) -> bool {
+        true
+    }
+
+    /// Absolute value of the number in `f64`, if available (see
+    /// [`Graph::to_dot_with_gradients`](crate::graph::Graph::to_dot_with_gradients)).
+    #[inline]
+    fn magnitude(&self) -> Option<f64> {
+        None
+    }
 }
-impl Number for i8 {}
-impl Number for i16 {}
-impl Number for i32 {}
-impl Number for i64 {}
-impl Number for f32 {}
-impl Number for f64 {}
-impl Number for num::complex::Complex<f32> {}
-impl Number for num::complex::Complex<f64> {}
+
+macro_rules! impl_number {
+    ($t:ty, |$x:ident| $magnitude:expr) => {
+        impl Number for $t {
+            #[inline]
+            fn magnitude(&self) -> Option<f64> {
+                let $x = *self;
+                Some($magnitude)
+            }
+        }
+    };
+    ($t:ty, finite, |$x:ident| $magnitude:expr) => {
+        impl Number for $t {
+            #[inline]
+            fn is_finite_number(&self) -> bool {
+                self.is_finite()
+            }
+
+            #[inline]
+            fn magnitude(&self) -> Option<f64> {
+                let $x = *self;
+                Some($magnitude)
+            }
+        }
+    };
+}
+
+impl_number!(i8, |x| f64::from(x).abs());
+impl_number!(i16, |x| f64::from(x).abs());
+impl_number!(i32, |x| f64::from(x).abs());
+impl_number!(i64, |x| (x as f64).abs());
+impl_number!(f32, finite, |x| f64::from(x).abs());
+impl_number!(f64, finite, |x| x.abs());
+impl_number!(num::complex::Complex<f32>, finite, |x| f64::from(x.norm()));
+impl_number!(num::complex::Complex<f64>, finite, |x| x.norm());
 impl Number for num::Rational32 {}
 impl Number for num::Rational64 {}
 
@@ -588,4 +741,5 @@ mod testing {
     trait Test: Sync + Send + Clone + Default {}
     impl Test for Graph1 {}
     impl Test for GraphN {}
+    impl Test for Forward1 {}
 }
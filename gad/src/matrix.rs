@@ -6,7 +6,7 @@ use crate::{
     error::Result,
     graph::{Config1, ConfigN, Graph, Value},
     linked::LinkedAlgebra,
-    store::GradientStore,
+    store::{GradientStore1, GradientStoreN},
 };
 
 /// Whether a matrix should be transposed and/or conjugated before applying a matrix operation.
@@ -139,14 +139,15 @@ mod af_arith {
 }
 
 macro_rules! impl_graph {
-    ($config:ident) => {
-        impl<D, E, Dims> MatrixAlgebra<Value<D>> for Graph<$config<E>>
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> MatrixAlgebra<Value<D>> for Graph<$config<E, S>>
         where
             E: Default
                 + Clone
                 + CoreAlgebra<D, Value = D>
                 + LinkedAlgebra<Value<D>, D>
                 + MatrixAlgebra<D>,
+            S: $store<D>,
             D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
             Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
         {
@@ -157,30 +158,39 @@ macro_rules! impl_graph {
                 prop1: MatProp,
                 prop2: MatProp,
             ) -> Result<Value<D>> {
-                let result = self.eval().matmul(v1.data(), v2.data(), prop1, prop2)?;
-                let value = self.make_node(result, vec![v1.input(), v2.input()], {
-                    let v1 = v1.clone();
-                    let v2 = v2.clone();
-                    move |graph, store, gradient| {
-                        if let Some(id) = v1.id() {
-                            let c2 = graph.link(&v2);
-                            let grad = graph.matmul(&gradient, c2, prop1, prop2.transpose())?;
-                            store.add_gradient(graph, id, &grad)?;
-                        }
-                        if let Some(id) = v2.id() {
-                            let c1 = graph.link(&v1);
-                            let grad = graph.matmul(c1, &gradient, prop1.transpose(), prop2)?;
-                            store.add_gradient(graph, id, &grad)?;
+                let result = self.eval_timed("matmul", |eval| {
+                    eval.matmul(v1.data(), v2.data(), prop1, prop2)
+                })?;
+                let value = self.make_named_node(
+                    "matmul",
+                    result,
+                    vec![v1.input(), v2.input()],
+                    v1.size_in_bytes() + v2.size_in_bytes(),
+                    {
+                        let v1 = v1.clone();
+                        let v2 = v2.clone();
+                        move |graph, store, gradient| {
+                            if let Some(id) = v1.id() {
+                                let c2 = graph.link(&v2);
+                                let grad = graph.matmul(&gradient, c2, prop1, prop2.transpose())?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            if let Some(id) = v2.id() {
+                                let c1 = graph.link(&v1);
+                                let grad = graph.matmul(c1, &gradient, prop1.transpose(), prop2)?;
+                                store.add_gradient(graph, id, &grad)?;
+                            }
+                            Ok(())
                         }
-                        Ok(())
-                    }
-                });
+                    },
+                );
                 Ok(value)
             }
 
             fn transpose(&mut self, v: &Value<D>, conjugate: bool) -> Result<Value<D>> {
-                let result = self.eval().transpose(v.data(), conjugate)?;
-                let value = self.make_node(result, vec![v.input()], {
+                let result =
+                    self.eval_timed("transpose", |eval| eval.transpose(v.data(), conjugate))?;
+                let value = self.make_named_node("transpose", result, vec![v.input()], 0, {
                     let id = v.id();
                     move |graph, store, gradient| {
                         if let Some(id) = id {
@@ -196,8 +206,8 @@ macro_rules! impl_graph {
     };
 }
 
-impl_graph!(Config1);
-impl_graph!(ConfigN);
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
 
 impl MatProp {
     #[inline]
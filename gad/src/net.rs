@@ -4,9 +4,9 @@
 use crate::{
     core::{CoreAlgebra, HasDims},
     error::{check_equal_dimensions, check_equal_lengths, Error, Result},
-    graph,
-    store::GradientReader,
-    Check, Eval,
+    graph::{self, Config1, Graph, Value},
+    store::{GradientId, GradientReader, GradientStore, GradientStore1},
+    Check, Eval, Number,
 };
 use serde::{Deserialize, Serialize};
 
@@ -62,6 +62,23 @@ pub trait Net<Algebra: HasGradientReader> {
         Ok(self.eval_with_gradient_info(graph, input)?.0)
     }
 
+    /// Evaluate the network and store the gradient information in the given slot, typically
+    /// a field of the gradient information of an enclosing network (see `#[derive(Net)]`).
+    /// * Fails if the slot is already used, i.e. if the network is evaluated twice.
+    fn eval_into(
+        &self,
+        graph: &mut Algebra,
+        input: Self::Input,
+        slot: &mut Option<Self::GradientInfo>,
+    ) -> Result<Self::Output> {
+        if slot.is_some() {
+            return Err(Error::repeated_evaluation(func_name!()));
+        }
+        let (output, info) = self.eval_with_gradient_info(graph, input)?;
+        *slot = Some(info);
+        Ok(output)
+    }
+
     fn map<F, O>(self, f: F) -> Map<Self, F>
     where
         Self: Sized,
@@ -93,6 +110,15 @@ pub trait Net<Algebra: HasGradientReader> {
     {
         (self, net)
     }
+
+    /// Do not keep the intermediate nodes of this network on the tape. Instead, the
+    /// network is evaluated again during backward propagation (see [`Checkpoint`]).
+    fn checkpoint(self) -> Checkpoint<Self>
+    where
+        Self: Sized,
+    {
+        Checkpoint(self)
+    }
 }
 
 /// Operations supported by weight types [`Net::Weights`]
@@ -103,6 +129,39 @@ pub trait WeightOps<T>: serde::Serialize + serde::de::DeserializeOwned + Clone +
     fn scale(&self, lambda: T) -> Self;
 }
 
+/// Conversion of weight types [`Net::Weights`] from and to another precision `M`
+/// (e.g. reduced-precision numbers to and from `f32`).
+/// * Used by [`crate::net_ext::MixedPrecision`] to keep a full-precision copy of the weights.
+pub trait CastWeights<M>: Sized {
+    /// Convert weights given in the precision `M`.
+    fn cast_from(weights: &M) -> Self;
+
+    /// Convert weights (or gradients) to the precision `M`.
+    fn cast_into(&self) -> M;
+}
+
+/// Scalar weights.
+impl<T: Number> WeightOps<T> for T {
+    fn add_assign(&mut self, other: Self) -> Result<()> {
+        *self += other;
+        Ok(())
+    }
+
+    fn scale(&self, lambda: T) -> Self {
+        *self * lambda
+    }
+}
+
+impl<T: Number> CastWeights<T> for T {
+    fn cast_from(weights: &T) -> Self {
+        *weights
+    }
+
+    fn cast_into(&self) -> T {
+        *self
+    }
+}
+
 impl<C: graph::Config> HasGradientReader for graph::Graph<C> {
     type GradientReader = C::GradientStore;
 }
@@ -180,6 +239,15 @@ mod af_net {
     }
 }
 
+impl<T: Number> HasGradientId for T {
+    type GradientId = ();
+
+    #[inline]
+    fn gid(&self) -> Result<Self::GradientId> {
+        Ok(())
+    }
+}
+
 impl<A> HasGradientId for graph::Value<A> {
     type GradientId = crate::store::GradientId<A>;
 
@@ -372,7 +440,7 @@ where
         reader: &Algebra::GradientReader,
     ) -> Result<Self::Weights> {
         let data = reader
-            .read(info)
+            .try_read(info)?
             .ok_or_else(|| Error::missing_gradient(func_name!()))?
             .clone();
         Ok(data)
@@ -491,6 +559,20 @@ where
     }
 }
 
+impl<M1, M2, W1, W2> CastWeights<Then<M1, M2>> for Then<W1, W2>
+where
+    W1: CastWeights<M1>,
+    W2: CastWeights<M2>,
+{
+    fn cast_from(weights: &Then<M1, M2>) -> Self {
+        Then(W1::cast_from(&weights.0), W2::cast_from(&weights.1))
+    }
+
+    fn cast_into(&self) -> Then<M1, M2> {
+        Then(self.0.cast_into(), self.1.cast_into())
+    }
+}
+
 /// The result of [`Net::using`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Using<N1, N2>(pub N1, pub N2);
@@ -558,8 +640,153 @@ where
     }
 }
 
+impl<M1, M2, W1, W2> CastWeights<Using<M1, M2>> for Using<W1, W2>
+where
+    W1: CastWeights<M1>,
+    W2: CastWeights<M2>,
+{
+    fn cast_from(weights: &Using<M1, M2>) -> Self {
+        Using(W1::cast_from(&weights.0), W2::cast_from(&weights.1))
+    }
+
+    fn cast_into(&self) -> Using<M1, M2> {
+        Using(self.0.cast_into(), self.1.cast_into())
+    }
+}
+
+/// The result of [`Net::checkpoint`]
+/// * With first-order graphs `Graph<Config1<E, S>>` (e.g. [`Graph1`]), the forward pass of the
+/// inner network is computed with the evaluation algebra `E` of the graph (e.g. [`Eval`]),
+/// hence the inner network must also implement `Net<E>` with the same weights. A single node
+/// is added to the main graph. During backward propagation, the inner network is evaluated
+/// again on a temporary graph from a copy of the input data, then used to compute the
+/// gradients of the input and of the weights.
+/// * Weight gradients are written in the gradient store of the main graph.
+/// * Checkpoints are first-order only: there is no implementation for higher-order graphs
+/// (e.g. [`GraphN`]).
+/// * With the algebras [`Eval`] and [`Check`], this is equivalent to the inner network.
+/// * The inner network must be deterministic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint<N>(N);
+
+impl<N, D, W, Dims, E, S> Net<Graph<Config1<E, S>>> for Checkpoint<N>
+where
+    N: Net<Graph<Config1<E, S>>, Input = Value<D>, Output = Value<D>, Weights = W>
+        + Net<E, Input = D, Output = D, Weights = W>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    W: Clone + Send + Sync + 'static,
+    E: HasGradientReader + Default + Clone + CoreAlgebra<D, Value = D>,
+    S: GradientStore1<D> + GradientStore<GradientId<W>, W> + Send + Sync + 'static,
+    D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
+    Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
+{
+    type Input = Value<D>;
+    type Output = Value<D>;
+    type Weights = W;
+    type GradientInfo = Option<GradientId<W>>;
+
+    fn eval_with_gradient_info(
+        &self,
+        graph: &mut Graph<Config1<E, S>>,
+        input: Self::Input,
+    ) -> Result<(Self::Output, Self::GradientInfo)> {
+        let data = graph.eval_timed("checkpoint", |eval| {
+            Net::<E>::eval(&self.0, eval, input.data().clone())
+        })?;
+        let slot = graph.make_gradient_slot::<W>("checkpoint_weights");
+        let inputs = vec![input.input(), slot.map(|slot| slot.inner)];
+        // The weights captured with the network are not counted.
+        let retained_bytes = input.size_in_bytes();
+        let output = graph.make_named_node("checkpoint", data, inputs, retained_bytes, {
+            let net = self.0.clone();
+            let data = input.data().clone();
+            let id = input.id();
+            move |graph, store, gradient| {
+                let mut g = Graph::<Config1<E, S>>::new();
+                let x = g.variable(data.clone());
+                let (y, info) = net.eval_with_gradient_info(&mut g, x.clone())?;
+                let gradients = g.evaluate_gradients_once(y.gid()?, gradient)?;
+                if let Some(id) = id {
+                    if let Some(dx) = gradients.get(x.gid()?) {
+                        store.add_gradient::<D, _>(graph, id, dx)?;
+                    }
+                }
+                if let Some(slot) = slot {
+                    let weights =
+                        Net::<Graph<Config1<E, S>>>::read_weight_gradients(&net, info, &gradients)?;
+                    store.insert(slot, weights);
+                }
+                Ok(())
+            }
+        });
+        Ok((output, slot))
+    }
+
+    fn get_weights(&self) -> Self::Weights {
+        Net::<E>::get_weights(&self.0)
+    }
+
+    fn set_weights(&mut self, weights: Self::Weights) -> Result<()> {
+        Net::<E>::set_weights(&mut self.0, weights)
+    }
+
+    fn update_weights(&mut self, delta: Self::Weights) -> Result<()> {
+        Net::<E>::update_weights(&mut self.0, delta)
+    }
+
+    fn read_weight_gradients(&self, info: Self::GradientInfo, reader: &S) -> Result<Self::Weights> {
+        info.and_then(|slot| reader.read(slot).cloned())
+            .ok_or_else(|| Error::missing_gradient(func_name!()))
+    }
+}
+
+macro_rules! impl_checkpoint {
+    ($algebra:ident) => {
+        impl<N: Net<$algebra>> Net<$algebra> for Checkpoint<N> {
+            type Input = N::Input;
+            type Output = N::Output;
+            type Weights = N::Weights;
+            type GradientInfo = N::GradientInfo;
+
+            fn eval_with_gradient_info(
+                &self,
+                graph: &mut $algebra,
+                input: Self::Input,
+            ) -> Result<(Self::Output, Self::GradientInfo)> {
+                self.0.eval_with_gradient_info(graph, input)
+            }
+
+            fn get_weights(&self) -> Self::Weights {
+                self.0.get_weights()
+            }
+
+            fn set_weights(&mut self, weights: Self::Weights) -> Result<()> {
+                self.0.set_weights(weights)
+            }
+
+            fn update_weights(&mut self, delta: Self::Weights) -> Result<()> {
+                self.0.update_weights(delta)
+            }
+
+            fn read_weight_gradients(
+                &self,
+                info: Self::GradientInfo,
+                reader: &<$algebra as HasGradientReader>::GradientReader,
+            ) -> Result<Self::Weights> {
+                self.0.read_weight_gradients(info, reader)
+            }
+        }
+    };
+}
+
+impl_checkpoint!(Eval);
+impl_checkpoint!(Check);
+
 macro_rules! impl_net_tuple {
-        ( $($name:ident $idx:tt)*) => (
+        ( $($name:ident $master:ident $idx:tt)*) => (
 impl<Algebra: HasGradientReader, $($name: Net<Algebra>),*> Net<Algebra> for ($($name,)*)
 {
     type Input = ($($name::Input,)*);
@@ -614,19 +841,32 @@ where
         ($(self.$idx.scale(_rhs),)*)
     }
 }
+
+impl<$($master, $name),*> CastWeights<($($master,)*)> for ($($name,)*)
+where
+    $($name: CastWeights<$master>),*
+{
+    fn cast_from(_weights: &($($master,)*)) -> Self {
+        ($($name::cast_from(&_weights.$idx),)*)
+    }
+
+    fn cast_into(&self) -> ($($master,)*) {
+        ($(self.$idx.cast_into(),)*)
+    }
+}
 )}
 
 impl_net_tuple! {}
-impl_net_tuple! { A 0 }
-impl_net_tuple! { A 0 B 1 }
-impl_net_tuple! { A 0 B 1 C 2 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 F 5 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 }
-impl_net_tuple! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 J 9}
+impl_net_tuple! { A MA 0 }
+impl_net_tuple! { A MA 0 B MB 1 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 F MF 5 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 F MF 5 G MG 6 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 F MF 5 G MG 6 H MH 7 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 F MF 5 G MG 6 H MH 7 I MI 8 }
+impl_net_tuple! { A MA 0 B MB 1 C MC 2 D MD 3 E ME 4 F MF 5 G MG 6 H MH 7 I MI 8 J MJ 9}
 
 impl<Algebra, N> Net<Algebra> for Vec<N>
 where
@@ -702,3 +942,16 @@ where
         self.iter().map(|x| x.scale(rhs)).collect()
     }
 }
+
+impl<M, W> CastWeights<Vec<M>> for Vec<W>
+where
+    W: CastWeights<M>,
+{
+    fn cast_from(weights: &Vec<M>) -> Self {
+        weights.iter().map(W::cast_from).collect()
+    }
+
+    fn cast_into(&self) -> Vec<M> {
+        self.iter().map(W::cast_into).collect()
+    }
+}
@@ -2,14 +2,19 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    analytic::AnalyticAlgebra,
     arith::ArithAlgebra,
     array::ArrayAlgebra,
+    compare::CompareAlgebra,
+    const_arith::ConstArithAlgebra,
     core::{CoreAlgebra, HasDims},
-    error::{check_equal_dimensions, Error, Result},
+    error::{check_equal_dimensions, AnomalyKind, Error, Result},
     graph::Value,
     matrix::MatrixAlgebra,
-    net::{HasGradientId, HasGradientReader, Net, WeightOps},
-    Graph1, Number,
+    net::{CastWeights, HasGradientId, HasGradientReader, Net, WeightOps},
+    store::{GenericGradientMap1, GradientStore},
+    trace::Trace,
+    Eval, Graph1, Number,
 };
 use serde::{Deserialize, Serialize};
 
@@ -71,6 +76,18 @@ where
         // Report cumulated error
         cumulated_output.ok_or_else(|| Error::empty(func_name!()))
     }
+
+    /// Train the network in the precision `T` (e.g. `F16` with the feature `half`) while keeping
+    /// a copy of the weights in `f32`. See [`MixedPrecision`].
+    fn mixed_precision<M>(self, loss_scale: f32) -> MixedPrecision<Self, M>
+    where
+        Self: Sized,
+        T: CastWeights<f32>,
+        Self::Weights: CastWeights<M>,
+        M: WeightOps<f32>,
+    {
+        MixedPrecision::new(self, loss_scale)
+    }
 }
 
 impl<N, T> DiffNet<T> for N
@@ -81,6 +98,84 @@ where
 {
 }
 
+/// Extension trait when the algebra is [`crate::Graph1`] and the network only provides the
+/// weights of a model whose loss is recorded as a [`crate::trace::Trace`].
+pub trait TracedNet<T>: Net<Graph1, Output = Vec<Value<T>>>
+where
+    T: Number,
+    Self::Weights: WeightOps<T>,
+{
+    /// Apply a "mini-batch" gradient step by evaluating the recorded loss `trace` on every
+    /// example of the batch.
+    /// * The network is evaluated once on `input` (e.g. `vec![(); n]` for a vector of `n`
+    /// weights) to obtain the current weights.
+    /// * The inputs of the trace are the outputs of the network followed by the data of the
+    /// example.
+    /// * Examples are evaluated with the buffers of the trace (see
+    /// [`crate::trace::Trace::eval_with_gradients`]): no graph node is created for the
+    /// operations of the trace.
+    /// * `lambda` is expected to be negative for loss minimization.
+    fn apply_traced_gradient_step<C>(
+        &mut self,
+        input: Self::Input,
+        trace: &mut Trace<T, C>,
+        lambda: T,
+        batch: Vec<Vec<T>>,
+    ) -> Result<T>
+    where
+        Eval: AnalyticAlgebra<T> + ConstArithAlgebra<T, C> + CompareAlgebra<T>,
+        C: std::ops::Sub<C, Output = C> + num::One + Clone,
+    {
+        let mut g = Graph1::new();
+        let (weights, info) = self.eval_with_gradient_info(&mut g, input)?;
+        let num_weights = weights.len();
+        let mut inputs = weights.iter().map(|w| *w.data()).collect::<Vec<_>>();
+        let mut gradients = vec![None; num_weights];
+        let mut cumulated_output: Option<T> = None;
+        let mut eval = Eval::default();
+        for example in batch {
+            // Forward and backward passes
+            inputs.truncate(num_weights);
+            inputs.extend(example);
+            let (output, input_gradients) =
+                trace.eval_with_gradients(&mut eval, &inputs, T::one())?;
+            match &mut cumulated_output {
+                opt @ None => *opt = Some(output),
+                Some(val) => *val += output,
+            }
+            // Accumulate gradient.
+            for (sum, gradient) in gradients.iter_mut().zip(input_gradients) {
+                if let Some(gradient) = gradient {
+                    match sum {
+                        opt @ None => *opt = Some(gradient),
+                        Some(val) => *val += gradient,
+                    }
+                }
+            }
+        }
+        let cumulated_output = cumulated_output.ok_or_else(|| Error::empty(func_name!()))?;
+        // Update weights.
+        let mut store = GenericGradientMap1::default();
+        for (weight, gradient) in weights.iter().zip(gradients) {
+            if let (Some(id), Some(gradient)) = (weight.id(), gradient) {
+                store.insert(id, gradient);
+            }
+        }
+        let gradients = self.read_weight_gradients(info, &store)?;
+        self.update_weights(gradients.scale(lambda))?;
+        // Report cumulated error
+        Ok(cumulated_output)
+    }
+}
+
+impl<N, T> TracedNet<T> for N
+where
+    T: Number,
+    N: Net<Graph1, Output = Vec<Value<T>>>,
+    N::Weights: WeightOps<T>,
+{
+}
+
 /// The result of [`SingleOutputNet::add_square_loss`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SquareLoss<N, Data>(N, std::marker::PhantomData<Data>);
@@ -138,3 +233,158 @@ where
         self.0.read_weight_gradients(info, store)
     }
 }
+
+/// The result of [`DiffNet::mixed_precision`].
+/// * Forward and backward passes run in the precision of the network, while gradient
+/// steps are applied to "master" weights `M` in `f32`, then copied back to the network.
+/// * The loss is multiplied by a loss scale during the backward pass so that small
+/// gradients do not underflow. Gradients are divided by the same scale in `f32`.
+/// * When a gradient overflows (i.e. is NaN or infinite), the step is skipped and the
+/// loss scale is halved, down to [`MixedPrecision::MIN_LOSS_SCALE`]. The loss scale is
+/// doubled after a number of successful steps, as long as it remains finite in the precision
+/// of the network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixedPrecision<N, M> {
+    net: N,
+    master: M,
+    loss_scale: f32,
+    growth_interval: usize,
+    good_steps: usize,
+}
+
+impl<N, M> MixedPrecision<N, M> {
+    /// Default number of successful steps before the loss scale is doubled.
+    pub const DEFAULT_GROWTH_INTERVAL: usize = 2000;
+
+    /// Smallest loss scale. Gradients that overflow at this scale are reported as errors.
+    pub const MIN_LOSS_SCALE: f32 = 1.0;
+
+    /// Create a mixed-precision trainer with the given initial loss scale.
+    /// * The loss scale is halved until it is finite in the precision `T`, and is at least
+    /// [`MixedPrecision::MIN_LOSS_SCALE`].
+    pub fn new<T>(net: N, loss_scale: f32) -> Self
+    where
+        T: Number + CastWeights<f32>,
+        N: Net<Graph1, Output = Value<T>>,
+        N::Weights: CastWeights<M>,
+    {
+        let master = net.get_weights().cast_into();
+        Self {
+            net,
+            master,
+            loss_scale: Self::clamp_loss_scale::<T>(loss_scale),
+            growth_interval: Self::DEFAULT_GROWTH_INTERVAL,
+            good_steps: 0,
+        }
+    }
+
+    /// Set the number of successful steps before the loss scale is doubled.
+    pub fn with_growth_interval(mut self, growth_interval: usize) -> Self {
+        self.growth_interval = growth_interval;
+        self
+    }
+
+    /// The trained network.
+    pub fn net(&self) -> &N {
+        &self.net
+    }
+
+    /// Consume the trainer and return the trained network.
+    pub fn into_net(self) -> N {
+        self.net
+    }
+
+    /// The full-precision weights.
+    pub fn master_weights(&self) -> &M {
+        &self.master
+    }
+
+    /// The current loss scale.
+    pub fn loss_scale(&self) -> f32 {
+        self.loss_scale
+    }
+
+    /// Halve `loss_scale` until it is finite in the precision `T`, without going below
+    /// `MIN_LOSS_SCALE`.
+    fn clamp_loss_scale<T>(loss_scale: f32) -> f32
+    where
+        T: Number + CastWeights<f32>,
+    {
+        // This also replaces NaN.
+        let mut loss_scale = loss_scale.max(Self::MIN_LOSS_SCALE);
+        while loss_scale > Self::MIN_LOSS_SCALE && !T::cast_from(&loss_scale).is_finite_number() {
+            loss_scale = (loss_scale / 2.0).max(Self::MIN_LOSS_SCALE);
+        }
+        loss_scale
+    }
+
+    /// Apply a "mini-batch" gradient step to the master weights, then update the network.
+    /// * `lambda` is expected to be negative for loss minimization.
+    /// * Return the cumulated loss in `f32`, or `None` if the step was skipped because a
+    /// gradient overflowed.
+    /// * Gradients that overflow at the smallest loss scale are reported as errors
+    /// ([`Error::Anomaly`]).
+    /// * Non-finite values in the forward pass are reported as errors
+    /// ([`Error::Anomaly`]) since they do not depend on the loss scale.
+    pub fn apply_gradient_step<T>(
+        &mut self,
+        lambda: f32,
+        batch: Vec<N::Input>,
+    ) -> Result<Option<f32>>
+    where
+        T: Number + CastWeights<f32>,
+        N: Net<Graph1, Output = Value<T>>,
+        N::Weights: CastWeights<M>,
+        M: WeightOps<f32>,
+    {
+        // Deserialized trainers may hold any loss scale.
+        self.loss_scale = Self::clamp_loss_scale::<T>(self.loss_scale);
+        let loss_scale = T::cast_from(&self.loss_scale);
+        let mut delta: Option<M> = None;
+        let mut cumulated_output: Option<f32> = None;
+        for example in batch {
+            // Forward pass
+            let mut g = Graph1::new();
+            g.enable_anomaly_detection();
+            let (output, info) = self.net.eval_with_gradient_info(&mut g, example)?;
+            let loss = output.data().cast_into();
+            match &mut cumulated_output {
+                opt @ None => *opt = Some(loss),
+                Some(val) => *val += loss,
+            }
+            // Backward pass on the scaled loss.
+            let store = match g.evaluate_gradients_once(output.gid()?, loss_scale) {
+                Ok(store) => store,
+                Err(Error::Anomaly {
+                    kind: AnomalyKind::Gradient,
+                    ..
+                }) if self.loss_scale > Self::MIN_LOSS_SCALE => {
+                    self.loss_scale = (self.loss_scale / 2.0).max(Self::MIN_LOSS_SCALE);
+                    self.good_steps = 0;
+                    return Ok(None);
+                }
+                Err(error) => return Err(error),
+            };
+            // Accumulate unscaled gradients in full precision.
+            let gradients: M = self.net.read_weight_gradients(info, &store)?.cast_into();
+            let gradients = gradients.scale(lambda / self.loss_scale);
+            match &mut delta {
+                opt @ None => *opt = Some(gradients),
+                Some(val) => val.add_assign(gradients)?,
+            }
+        }
+        let cumulated_output = cumulated_output.ok_or_else(|| Error::empty(func_name!()))?;
+        // Update master weights, then the network.
+        if let Some(delta) = delta {
+            self.master.add_assign(delta)?;
+            self.net.set_weights(N::Weights::cast_from(&self.master))?;
+        }
+        self.good_steps += 1;
+        if self.good_steps >= self.growth_interval {
+            self.loss_scale = Self::clamp_loss_scale::<T>(self.loss_scale * 2.0);
+            self.good_steps = 0;
+        }
+        // Report cumulated error
+        Ok(Some(cumulated_output))
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[cfg(doc)]
+use crate::prelude::*;
+
+/// Statistics collected for the nodes created by a single operator (e.g. "mul").
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperatorProfile {
+    /// Number of nodes created by the operator.
+    pub nodes: usize,
+    /// Total size in bytes of the values captured by the gradient-update closures of these
+    /// nodes (see [`HasDims::size_in_bytes`]).
+    /// * These values are kept alive until the graph is dropped.
+    /// * Nodes created with [`Graph::make_node`] and variables report 0.
+    pub retained_bytes: usize,
+    /// Time spent computing forward values.
+    pub forward_time: Duration,
+    /// Number of calls to the gradient-update closures during backward propagation.
+    pub backward_calls: usize,
+    /// Time spent in the gradient-update closures during backward propagation.
+    pub backward_time: Duration,
+}
+
+/// Statistics collected by a profiled [`Graph`], indexed by the names of the operators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    operators: BTreeMap<&'static str, OperatorProfile>,
+}
+
+/// State of the profiling layer of a graph.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Profiler {
+    profile: Arc<Mutex<Profile>>,
+}
+
+/// Statistics shared with the gradient-update closures of the profiled nodes.
+pub(crate) struct SharedProfile(Arc<Mutex<Profile>>);
+
+impl OperatorProfile {
+    /// Total time spent in forward and backward computations.
+    pub fn total_time(&self) -> Duration {
+        self.forward_time + self.backward_time
+    }
+}
+
+impl Profile {
+    /// Statistics for the operator `op`, if it was used since profiling was enabled.
+    pub fn get(&self, op: &str) -> Option<&OperatorProfile> {
+        self.operators.get(op)
+    }
+
+    /// Iterate on the statistics of all operators, by alphabetical order.
+    pub fn operators(&self) -> impl Iterator<Item = (&'static str, &OperatorProfile)> {
+        self.operators.iter().map(|(op, profile)| (*op, profile))
+    }
+
+    /// Sum of the statistics of all operators.
+    pub fn total(&self) -> OperatorProfile {
+        let mut total = OperatorProfile::default();
+        for profile in self.operators.values() {
+            total.nodes += profile.nodes;
+            total.retained_bytes += profile.retained_bytes;
+            total.forward_time += profile.forward_time;
+            total.backward_calls += profile.backward_calls;
+            total.backward_time += profile.backward_time;
+        }
+        total
+    }
+
+    /// A human-readable table of the statistics, sorted by decreasing total time.
+    pub fn report(&self) -> String {
+        let mut operators = self.operators().collect::<Vec<_>>();
+        operators.sort_by(|(op1, p1), (op2, p2)| {
+            p2.total_time().cmp(&p1.total_time()).then(op1.cmp(op2))
+        });
+        let total = self.total();
+        let mut report = format!(
+            "{:<16} {:>10} {:>14} {:>14} {:>10} {:>14}\n",
+            "operator", "nodes", "retained bytes", "forward (us)", "backward", "backward (us)"
+        );
+        for (op, profile) in operators
+            .into_iter()
+            .chain(std::iter::once(("total", &total)))
+        {
+            report += &format!(
+                "{:<16} {:>10} {:>14} {:>14} {:>10} {:>14}\n",
+                op,
+                profile.nodes,
+                profile.retained_bytes,
+                profile.forward_time.as_micros(),
+                profile.backward_calls,
+                profile.backward_time.as_micros()
+            );
+        }
+        report
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", self.report())
+    }
+}
+
+impl Profiler {
+    /// A copy of the statistics collected so far.
+    pub(crate) fn profile(&self) -> Profile {
+        self.profile.lock().unwrap().clone()
+    }
+
+    /// Run the forward computation `f` of the operator `op` and record the time spent.
+    #[inline]
+    pub(crate) fn time_forward<R, F: FnOnce() -> R>(&self, op: &'static str, f: F) -> R {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        let mut profile = self.profile.lock().unwrap();
+        profile.operators.entry(op).or_default().forward_time += elapsed;
+        result
+    }
+
+    /// Record the creation of a node by the operator `op` whose gradient-update closure retains
+    /// `retained_bytes` bytes of data.
+    pub(crate) fn record_node(&self, op: &'static str, retained_bytes: usize) {
+        let mut profile = self.profile.lock().unwrap();
+        let entry = profile.operators.entry(op).or_default();
+        entry.nodes += 1;
+        entry.retained_bytes += retained_bytes;
+    }
+
+    /// A handle on the statistics, used to time backward computations.
+    pub(crate) fn shared(&self) -> SharedProfile {
+        SharedProfile(self.profile.clone())
+    }
+}
+
+impl SharedProfile {
+    /// Run the gradient-update closure `f` of a node created by the operator `op` and record
+    /// the time spent.
+    pub(crate) fn time_backward<R, F: FnOnce() -> R>(&self, op: &'static str, f: F) -> R {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        let mut profile = self.0.lock().unwrap();
+        let entry = profile.operators.entry(op).or_default();
+        entry.backward_calls += 1;
+        entry.backward_time += elapsed;
+        result
+    }
+}
@@ -1,7 +1,11 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{core::CoreAlgebra, error::Result, graph::Value};
+use crate::{
+    core::CoreAlgebra,
+    error::{Error, Result},
+    graph::Value,
+};
 use std::collections::BTreeMap;
 
 #[cfg(doc)]
@@ -26,6 +30,12 @@ pub struct GradientId<T> {
 /// Value may be converted if needed.
 pub trait GradientReader<Id, T> {
     fn read(&self, id: Id) -> Option<&T>;
+
+    /// Same as `read` but report an [`Error::GradientType`] instead of panicking when the
+    /// gradient stored for `id` does not have the expected type.
+    fn try_read(&self, id: Id) -> Result<Option<&T>> {
+        Ok(self.read(id))
+    }
 }
 
 /// Trait for accessing gradient values of type `T` given a handle of type `Id`.
@@ -38,6 +48,18 @@ pub trait GradientStore<Id, T>: GradientReader<Id, T> {
 
     fn get_mut(&mut self, id: Id) -> Option<&mut T>;
 
+    /// Same as `get` but report an [`Error::GradientType`] instead of panicking when the
+    /// gradient stored for `id` does not have the expected type.
+    fn try_get(&self, id: Id) -> Result<Option<&T>> {
+        self.try_read(id)
+    }
+
+    /// Same as `get_mut` but report an [`Error::GradientType`] instead of panicking when the
+    /// gradient stored for `id` does not have the expected type.
+    fn try_get_mut(&mut self, id: Id) -> Result<Option<&mut T>> {
+        Ok(self.get_mut(id))
+    }
+
     /// Update a gradient during backward propagation. This is used to define operators
     /// together with [`Graph::make_node`].
     /// The parameter `graph` is used for higher-order differentials (see [`GraphN`]).
@@ -47,7 +69,7 @@ pub trait GradientStore<Id, T>: GradientReader<Id, T> {
         Id: Copy,
         T: Clone + 'static,
     {
-        match self.get_mut(id) {
+        match self.try_get_mut(id)? {
             None => self.insert(id, value.clone()),
             Some(current) => *current = graph.add(current, value)?,
         }
@@ -55,11 +77,34 @@ pub trait GradientStore<Id, T>: GradientReader<Id, T> {
     }
 }
 
+/// Gradient stores usable in the configuration [`Config1`], for gradients of type `T`.
+pub trait GradientStore1<T>: GradientStore<GradientId<T>, T> + Default {}
+
+impl<S, T> GradientStore1<T> for S where S: GradientStore<GradientId<T>, T> + Default {}
+
+/// Gradient stores usable in the configuration [`ConfigN`], for gradients of type `Value<T>`.
+pub trait GradientStoreN<T>:
+    GradientStore<GradientId<T>, Value<T>> + GradientReader<GradientId<T>, T> + Default
+{
+}
+
+impl<S, T> GradientStoreN<T> for S where
+    S: GradientStore<GradientId<T>, Value<T>> + GradientReader<GradientId<T>, T> + Default
+{
+}
+
+/// A gradient of any type, together with the name of its type.
+#[derive(Debug)]
+struct AnyGradient {
+    value: Box<dyn std::any::Any + Send + Sync>,
+    type_name: &'static str,
+}
+
 /// Gradient store used by [`Graph1`].
 /// Indices of type `GradientId<T>` are mapped to values of type `T`.
 #[derive(Debug)]
 pub struct GenericGradientMap1 {
-    values: BTreeMap<Id, Box<dyn std::any::Any>>,
+    values: BTreeMap<Id, AnyGradient>,
 }
 
 impl Default for GenericGradientMap1 {
@@ -70,25 +115,87 @@ impl Default for GenericGradientMap1 {
     }
 }
 
-impl<T: 'static> GradientReader<GradientId<T>, T> for GenericGradientMap1 {
+impl GenericGradientMap1 {
+    /// Drop the gradient of a node, if any.
+    pub(crate) fn remove(&mut self, id: Id) {
+        self.values.remove(&id);
+    }
+
+    /// Move the gradients of the given nodes, if any, to a new store.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn extract(&mut self, ids: impl Iterator<Item = Id>) -> Self {
+        let mut values = BTreeMap::new();
+        for id in ids {
+            if let Some(value) = self.values.remove(&id) {
+                values.insert(id, value);
+            }
+        }
+        Self { values }
+    }
+
+    /// Move all the gradients of another store to this one.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn append(&mut self, mut other: Self) {
+        self.values.append(&mut other.values);
+    }
+}
+
+impl AnyGradient {
+    fn new<T: 'static + Send + Sync>(value: T) -> Self {
+        Self {
+            value: Box::new(value),
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    fn downcast_ref<T: 'static>(&self) -> Result<&T> {
+        let type_name = self.type_name;
+        self.value
+            .downcast_ref::<T>()
+            .ok_or_else(|| Self::type_error::<T>(type_name))
+    }
+
+    fn downcast_mut<T: 'static>(&mut self) -> Result<&mut T> {
+        let type_name = self.type_name;
+        self.value
+            .downcast_mut::<T>()
+            .ok_or_else(|| Self::type_error::<T>(type_name))
+    }
+
+    fn type_error<T>(actual: &str) -> Error {
+        Error::gradient_type(func_name!(), std::any::type_name::<T>(), actual)
+    }
+}
+
+impl<T: 'static + Send + Sync> GradientReader<GradientId<T>, T> for GenericGradientMap1 {
     fn read(&self, id: GradientId<T>) -> Option<&T> {
-        self.values.get(&id.inner).map(|val| {
-            val.downcast_ref::<T>()
-                .expect("indices should have a unique type")
-        })
+        self.try_read(id)
+            .expect("indices should have a unique type")
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&T>> {
+        self.values
+            .get(&id.inner)
+            .map(AnyGradient::downcast_ref)
+            .transpose()
     }
 }
 
-impl<T: 'static> GradientStore<GradientId<T>, T> for GenericGradientMap1 {
+impl<T: 'static + Send + Sync> GradientStore<GradientId<T>, T> for GenericGradientMap1 {
     fn insert(&mut self, id: GradientId<T>, gradient: T) {
-        self.values.insert(id.inner, Box::new(gradient));
+        self.values.insert(id.inner, AnyGradient::new(gradient));
     }
 
     fn get_mut(&mut self, id: GradientId<T>) -> Option<&mut T> {
-        self.values.get_mut(&id.inner).map(|val| {
-            val.downcast_mut::<T>()
-                .expect("indices should have a unique type")
-        })
+        self.try_get_mut(id)
+            .expect("indices should have a unique type")
+    }
+
+    fn try_get_mut(&mut self, id: GradientId<T>) -> Result<Option<&mut T>> {
+        self.values
+            .get_mut(&id.inner)
+            .map(AnyGradient::downcast_mut)
+            .transpose()
     }
 }
 
@@ -96,7 +203,7 @@ impl<T: 'static> GradientStore<GradientId<T>, T> for GenericGradientMap1 {
 /// Indices of type `GradientId<T>` are mapped to values of type `Value<T>`.
 #[derive(Debug)]
 pub struct GenericGradientMapN {
-    values: BTreeMap<Id, Box<dyn std::any::Any>>,
+    values: BTreeMap<Id, AnyGradient>,
 }
 
 impl Default for GenericGradientMapN {
@@ -107,35 +214,179 @@ impl Default for GenericGradientMapN {
     }
 }
 
-impl<T: 'static> GradientReader<GradientId<T>, Value<T>> for GenericGradientMapN {
+impl<T: 'static + Send + Sync> GradientReader<GradientId<T>, Value<T>> for GenericGradientMapN {
     fn read(&self, id: GradientId<T>) -> Option<&Value<T>> {
-        self.values.get(&id.inner).map(|val| {
-            val.downcast_ref::<Value<T>>()
-                .expect("indices should have a unique type")
-        })
+        GradientReader::<_, Value<T>>::try_read(self, id)
+            .expect("indices should have a unique type")
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&Value<T>>> {
+        self.values
+            .get(&id.inner)
+            .map(AnyGradient::downcast_ref)
+            .transpose()
     }
 }
 
-impl<T: 'static> GradientReader<GradientId<T>, T> for GenericGradientMapN {
+impl<T: 'static + Send + Sync> GradientReader<GradientId<T>, T> for GenericGradientMapN {
     fn read(&self, id: GradientId<T>) -> Option<&T> {
-        self.values.get(&id.inner).map(|val| {
-            val.downcast_ref::<Value<T>>()
-                .expect("indices should have a unique type")
-                .data()
-        })
+        GradientReader::<_, T>::try_read(self, id).expect("indices should have a unique type")
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&T>> {
+        let value = GradientReader::<_, Value<T>>::try_read(self, id)?;
+        Ok(value.map(Value::data))
     }
 }
 
-impl<T: 'static> GradientStore<GradientId<T>, Value<T>> for GenericGradientMapN {
+impl<T: 'static + Send + Sync> GradientStore<GradientId<T>, Value<T>> for GenericGradientMapN {
     fn insert(&mut self, id: GradientId<T>, gradient: Value<T>) {
-        self.values.insert(id.inner, Box::new(gradient));
+        self.values.insert(id.inner, AnyGradient::new(gradient));
     }
 
     fn get_mut(&mut self, id: GradientId<T>) -> Option<&mut Value<T>> {
-        self.values.get_mut(&id.inner).map(|val| {
-            val.downcast_mut::<Value<T>>()
-                .expect("indices should have a unique type")
-        })
+        self.try_get_mut(id)
+            .expect("indices should have a unique type")
+    }
+
+    fn try_get_mut(&mut self, id: GradientId<T>) -> Result<Option<&mut Value<T>>> {
+        self.values
+            .get_mut(&id.inner)
+            .map(AnyGradient::downcast_mut)
+            .transpose()
+    }
+}
+
+/// Dense gradient store for [`Config1`] when all the gradients have the same type `T`.
+/// Gradients are indexed by the position of nodes in the graph, which avoids the lookups,
+/// boxing, and downcasting of [`GenericGradientMap1`].
+/// * The store remembers the graph of the first gradient inserted. Nodes of other graphs
+/// have no gradient and are reported as [`Error::ForeignNode`] by the checked accessors.
+/// Inserting a gradient for such a node has no effect.
+#[derive(Debug, Clone)]
+pub struct DenseGradientMap1<T> {
+    arena_id: Option<u32>,
+    values: Vec<Option<T>>,
+}
+
+impl<T> Default for DenseGradientMap1<T> {
+    fn default() -> Self {
+        Self {
+            arena_id: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Position of the node `id` in a dense store holding nodes of the graph `arena_id`, if any.
+#[inline]
+fn dense_index(arena_id: Option<u32>, id: Id) -> Result<usize> {
+    match arena_id {
+        Some(arena_id) if arena_id != id.arena_id => {
+            Err(Error::foreign_node(func_name!(), Some(id)))
+        }
+        _ => Ok(id.index()),
+    }
+}
+
+/// Insert a value in a dense store, at the position of the node `id`.
+/// * Values for nodes of another graph are dropped, consistently with the checked accessors.
+#[inline]
+fn dense_insert<T>(arena_id: &mut Option<u32>, values: &mut Vec<Option<T>>, id: Id, value: T) {
+    let index = match dense_index(*arena_id, id) {
+        Ok(index) => index,
+        Err(_) => return,
+    };
+    *arena_id = Some(id.arena_id);
+    if index >= values.len() {
+        values.resize_with(index + 1, || None);
+    }
+    values[index] = Some(value);
+}
+
+impl<T> GradientReader<GradientId<T>, T> for DenseGradientMap1<T> {
+    fn read(&self, id: GradientId<T>) -> Option<&T> {
+        self.try_read(id).ok().flatten()
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&T>> {
+        let index = dense_index(self.arena_id, id.inner)?;
+        Ok(self.values.get(index).and_then(Option::as_ref))
+    }
+}
+
+impl<T> GradientStore<GradientId<T>, T> for DenseGradientMap1<T> {
+    fn insert(&mut self, id: GradientId<T>, gradient: T) {
+        dense_insert(&mut self.arena_id, &mut self.values, id.inner, gradient);
+    }
+
+    fn get_mut(&mut self, id: GradientId<T>) -> Option<&mut T> {
+        self.try_get_mut(id).ok().flatten()
+    }
+
+    fn try_get_mut(&mut self, id: GradientId<T>) -> Result<Option<&mut T>> {
+        let index = dense_index(self.arena_id, id.inner)?;
+        Ok(self.values.get_mut(index).and_then(Option::as_mut))
+    }
+}
+
+/// Dense gradient store for [`ConfigN`] when all the gradients have the same type `Value<T>`.
+/// Gradients are indexed by the position of nodes in the graph, which avoids the lookups,
+/// boxing, and downcasting of [`GenericGradientMapN`].
+/// * The store remembers the graph of the first gradient inserted. Nodes of other graphs
+/// have no gradient and are reported as [`Error::ForeignNode`] by the checked accessors.
+/// Inserting a gradient for such a node has no effect.
+#[derive(Debug, Clone)]
+pub struct DenseGradientMapN<T> {
+    arena_id: Option<u32>,
+    values: Vec<Option<Value<T>>>,
+}
+
+impl<T> Default for DenseGradientMapN<T> {
+    fn default() -> Self {
+        Self {
+            arena_id: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> GradientReader<GradientId<T>, Value<T>> for DenseGradientMapN<T> {
+    fn read(&self, id: GradientId<T>) -> Option<&Value<T>> {
+        GradientReader::<_, Value<T>>::try_read(self, id)
+            .ok()
+            .flatten()
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&Value<T>>> {
+        let index = dense_index(self.arena_id, id.inner)?;
+        Ok(self.values.get(index).and_then(Option::as_ref))
+    }
+}
+
+impl<T> GradientReader<GradientId<T>, T> for DenseGradientMapN<T> {
+    fn read(&self, id: GradientId<T>) -> Option<&T> {
+        GradientReader::<_, T>::try_read(self, id).ok().flatten()
+    }
+
+    fn try_read(&self, id: GradientId<T>) -> Result<Option<&T>> {
+        let value = GradientReader::<_, Value<T>>::try_read(self, id)?;
+        Ok(value.map(Value::data))
+    }
+}
+
+impl<T> GradientStore<GradientId<T>, Value<T>> for DenseGradientMapN<T> {
+    fn insert(&mut self, id: GradientId<T>, gradient: Value<T>) {
+        dense_insert(&mut self.arena_id, &mut self.values, id.inner, gradient);
+    }
+
+    fn get_mut(&mut self, id: GradientId<T>) -> Option<&mut Value<T>> {
+        self.try_get_mut(id).ok().flatten()
+    }
+
+    fn try_get_mut(&mut self, id: GradientId<T>) -> Result<Option<&mut Value<T>>> {
+        let index = dense_index(self.arena_id, id.inner)?;
+        Ok(self.values.get_mut(index).and_then(Option::as_mut))
     }
 }
 
@@ -207,6 +458,18 @@ impl<T> PartialEq for GradientId<T> {
 impl<T> Eq for GradientId<T> {}
 
 impl Id {
+    /// Position of the node in the graph.
+    #[inline]
+    pub(crate) fn index(&self) -> usize {
+        <GraphArenaBehavior as id_arena::ArenaBehavior>::index(*self)
+    }
+
+    /// Identifier of the graph of the node.
+    #[inline]
+    pub(crate) fn arena_id(&self) -> u32 {
+        self.arena_id
+    }
+
     pub(crate) fn next_id(&self) -> Self {
         Self {
             arena_id: self.arena_id,
@@ -0,0 +1,283 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    analytic::AnalyticAlgebra,
+    arith::ArithAlgebra,
+    core::CoreAlgebra,
+    error::{Error, Result},
+};
+use std::cell::{RefCell, RefMut};
+
+#[cfg(doc)]
+use crate::prelude::*;
+
+/// An algebra (typically a tape such as [`Graph1`] or [`GraphN`]) shared by the values of
+/// type [`Var`].
+/// * Unlike other structures of the library, a tape is not `Sync`.
+#[derive(Debug, Default)]
+pub struct Tape<A>(RefCell<A>);
+
+/// A value recorded on a shared [`Tape`], supporting the usual operators `+`, `-`, `*`, `/`.
+/// * Operations are implicitly fallible: errors (e.g. incompatible dimensions) are propagated
+/// to the results and reported by [`Var::value`].
+/// * Operations between values of different tapes result in an error.
+pub struct Var<'a, A: CoreAlgebra<D>, D> {
+    tape: &'a Tape<A>,
+    value: Result<A::Value>,
+}
+
+impl<A> Tape<A> {
+    /// Create a shared tape from an algebra.
+    pub fn new(algebra: A) -> Self {
+        Self(RefCell::new(algebra))
+    }
+
+    /// Access the underlying algebra, e.g. to compute gradients.
+    /// * Panics if the algebra is already borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, A> {
+        self.0.borrow_mut()
+    }
+
+    /// Return the underlying algebra.
+    pub fn into_inner(self) -> A {
+        self.0.into_inner()
+    }
+
+    /// A differential input to the computation.
+    pub fn variable<D>(&self, data: D) -> Var<'_, A, D>
+    where
+        A: CoreAlgebra<D>,
+    {
+        let value = self.borrow_mut().variable(data);
+        Var::new(self, Ok(value))
+    }
+
+    /// A non-differential input to the computation.
+    pub fn constant<D>(&self, data: D) -> Var<'_, A, D>
+    where
+        A: CoreAlgebra<D>,
+    {
+        let value = self.borrow_mut().constant(data);
+        Var::new(self, Ok(value))
+    }
+
+    /// Wrap a value previously computed with the underlying algebra.
+    pub fn wrap<D>(&self, value: A::Value) -> Var<'_, A, D>
+    where
+        A: CoreAlgebra<D>,
+    {
+        Var::new(self, Ok(value))
+    }
+}
+
+impl<'a, A: CoreAlgebra<D>, D> Var<'a, A, D> {
+    fn new(tape: &'a Tape<A>, value: Result<A::Value>) -> Self {
+        Self { tape, value }
+    }
+
+    /// The tape of the value.
+    pub fn tape(&self) -> &'a Tape<A> {
+        self.tape
+    }
+
+    /// The underlying value, or the first error that occurred while computing it.
+    pub fn value(&self) -> Result<&A::Value> {
+        self.value.as_ref().map_err(Clone::clone)
+    }
+
+    /// Return the underlying value, or the first error that occurred while computing it.
+    pub fn into_value(self) -> Result<A::Value> {
+        self.value
+    }
+
+    fn unary<F>(&self, f: F) -> Self
+    where
+        F: FnOnce(&mut A, &A::Value) -> Result<A::Value>,
+    {
+        let value = match &self.value {
+            Ok(v) => f(&mut self.tape.borrow_mut(), v),
+            Err(e) => Err(e.clone()),
+        };
+        Self::new(self.tape, value)
+    }
+
+    fn binary<F>(&self, other: &Self, f: F) -> Self
+    where
+        F: FnOnce(&mut A, &A::Value, &A::Value) -> Result<A::Value>,
+    {
+        let value = match (&self.value, &other.value) {
+            _ if !std::ptr::eq(self.tape, other.tape) => {
+                Err(Error::foreign_node(func_name!(), None))
+            }
+            (Ok(v1), Ok(v2)) => f(&mut self.tape.borrow_mut(), v1, v2),
+            (Err(e), _) | (_, Err(e)) => Err(e.clone()),
+        };
+        Self::new(self.tape, value)
+    }
+
+    /// A non-differential copy of the value (see [`CoreAlgebra::detach`]).
+    pub fn detach(&self) -> Self
+    where
+        A::Value: Clone,
+    {
+        self.unary(|g, v| Ok(g.detach(v)))
+    }
+}
+
+macro_rules! impl_analytic_method {
+    ($($(#[$doc:meta])* $method:ident)*) => {
+        impl<'a, A, D> Var<'a, A, D>
+        where
+            A: CoreAlgebra<D> + AnalyticAlgebra<<A as CoreAlgebra<D>>::Value>,
+        {
+            $(
+                $(#[$doc])*
+                pub fn $method(&self) -> Self {
+                    self.unary(|g, v| Ok(g.$method(v)))
+                }
+            )*
+
+            /// Element-wise power `x ^ p`.
+            pub fn pow(&self, p: &Self) -> Self
+            where
+                A: ArithAlgebra<<A as CoreAlgebra<D>>::Value>,
+            {
+                self.binary(p, |g, v, p| g.pow(v, p))
+            }
+        }
+    };
+}
+
+impl_analytic_method! {
+    /// Element-wise exponential function `exp(x)`.
+    exp
+    /// Element-wise natural logarithm `log(x)`.
+    log
+    /// Element-wise function `log(1 + x)`.
+    log1p
+    /// Element-wise sinus function `sin(x)`.
+    sin
+    /// Element-wise cosinus function `cos(x)`.
+    cos
+    /// Element-wise hyperbolic tangent `tanh(x)`.
+    tanh
+    /// Element-wise sigmoid function `1 / (1 + exp(-x))`.
+    sigmoid
+    /// Element-wise reciprocal `1/x`.
+    reciprocal
+    /// Element-wise square root `sqrt(x)`.
+    sqrt
+}
+
+impl<'a, A, D> Clone for Var<'a, A, D>
+where
+    A: CoreAlgebra<D>,
+    A::Value: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.tape, self.value.clone())
+    }
+}
+
+impl<'a, A, D> std::fmt::Debug for Var<'a, A, D>
+where
+    A: CoreAlgebra<D>,
+    A::Value: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.debug_tuple("Var").field(&self.value).finish()
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $bound:path, $func:expr) => {
+        impl<'a, 'b, A, D> std::ops::$trait<&'b Var<'a, A, D>> for &'b Var<'a, A, D>
+        where
+            A: CoreAlgebra<D> + $bound,
+        {
+            type Output = Var<'a, A, D>;
+
+            fn $method(self, other: &'b Var<'a, A, D>) -> Var<'a, A, D> {
+                self.binary(other, $func)
+            }
+        }
+
+        impl<'a, 'b, A, D> std::ops::$trait<Var<'a, A, D>> for &'b Var<'a, A, D>
+        where
+            A: CoreAlgebra<D> + $bound,
+        {
+            type Output = Var<'a, A, D>;
+
+            fn $method(self, other: Var<'a, A, D>) -> Var<'a, A, D> {
+                self.binary(&other, $func)
+            }
+        }
+
+        impl<'a, 'b, A, D> std::ops::$trait<&'b Var<'a, A, D>> for Var<'a, A, D>
+        where
+            A: CoreAlgebra<D> + $bound,
+        {
+            type Output = Var<'a, A, D>;
+
+            fn $method(self, other: &'b Var<'a, A, D>) -> Var<'a, A, D> {
+                self.binary(other, $func)
+            }
+        }
+
+        impl<'a, A, D> std::ops::$trait<Var<'a, A, D>> for Var<'a, A, D>
+        where
+            A: CoreAlgebra<D> + $bound,
+        {
+            type Output = Var<'a, A, D>;
+
+            fn $method(self, other: Var<'a, A, D>) -> Var<'a, A, D> {
+                self.binary(&other, $func)
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, CoreAlgebra<D>, |g, v1, v2| CoreAlgebra::<D>::add(
+    g, v1, v2
+));
+impl_binary_op!(
+    Sub,
+    sub,
+    ArithAlgebra<<A as CoreAlgebra<D>>::Value>,
+    |g, v1, v2| g.sub(v1, v2)
+);
+impl_binary_op!(
+    Mul,
+    mul,
+    ArithAlgebra<<A as CoreAlgebra<D>>::Value>,
+    |g, v1, v2| g.mul(v1, v2)
+);
+impl_binary_op!(
+    Div,
+    div,
+    AnalyticAlgebra<<A as CoreAlgebra<D>>::Value>,
+    |g, v1, v2| g.div(v1, v2)
+);
+
+impl<'a, 'b, A, D> std::ops::Neg for &'b Var<'a, A, D>
+where
+    A: CoreAlgebra<D> + ArithAlgebra<<A as CoreAlgebra<D>>::Value>,
+{
+    type Output = Var<'a, A, D>;
+
+    fn neg(self) -> Var<'a, A, D> {
+        self.unary(|g, v| Ok(g.neg(v)))
+    }
+}
+
+impl<'a, A, D> std::ops::Neg for Var<'a, A, D>
+where
+    A: CoreAlgebra<D> + ArithAlgebra<<A as CoreAlgebra<D>>::Value>,
+{
+    type Output = Var<'a, A, D>;
+
+    fn neg(self) -> Var<'a, A, D> {
+        self.unary(|g, v| Ok(g.neg(v)))
+    }
+}
@@ -0,0 +1,869 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    analytic::AnalyticAlgebra,
+    arith::ArithAlgebra,
+    compare::CompareAlgebra,
+    const_arith::ConstArithAlgebra,
+    core::{CoreAlgebra, HasDims},
+    error::{check_equal_lengths, Error, Result},
+    matrix::MatProp,
+    Number,
+};
+
+#[cfg(doc)]
+use crate::prelude::*;
+
+/// An algebra that records a sequence of operations on placeholder inputs.
+/// * Values of this algebra do not contain data. They are indices in the recorded sequence.
+/// * The data of `constant` and `variable` is recorded in the trace. Only the inputs created
+/// by [`Trace::record`] are replaced during a replay.
+/// * Supported operations are those of [`CoreAlgebra`], [`ArithAlgebra`], [`AnalyticAlgebra`],
+/// [`ConstArithAlgebra`] (with constants of type `C`), and [`CompareAlgebra`]. Arrays
+/// (feature `arrayfire`) also support [`ArrayAlgebra`] and [`MatrixAlgebra`].
+pub struct Tracer<D: HasDims, C = i16> {
+    ops: Vec<Op<D, C>>,
+}
+
+/// A value recorded by a [`Tracer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TracedValue(usize);
+
+/// A scalar recorded by a [`Tracer`] (see [`ArrayAlgebra::Scalar`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TracedScalar(usize);
+
+/// The possible outputs of a [`Trace`]: [`TracedValue`] or [`TracedScalar`].
+pub trait Traced: Copy {
+    /// Position of the operation that computed this output.
+    fn index(self) -> usize;
+
+    /// Whether the output is a scalar.
+    fn is_scalar() -> bool;
+}
+
+/// A sequence of recorded operations that can be evaluated and differentiated many times.
+/// * Traces of scalars are evaluated and differentiated by [`Trace::eval`] and
+/// [`Trace::eval_with_gradients`]. These calls do not allocate nodes or closures: forward
+/// values and gradients are kept in internal buffers that are re-used by successive calls.
+/// * Any trace can also be replayed on another algebra (see [`Replay`]), e.g. a [`Graph`]
+/// to obtain higher-order gradients or the gradients of arrays.
+/// * Evaluating a trace does not call the formula that was recorded again. In particular,
+/// branches taken during the recording are fixed.
+pub struct Trace<D: HasDims, C = i16, O = TracedValue> {
+    ops: Vec<Op<D, C>>,
+    num_inputs: usize,
+    output: O,
+    /// Forward values of the last evaluation.
+    values: Vec<D>,
+    /// Gradients of the last backward pass.
+    gradients: Vec<Option<D>>,
+}
+
+/// Replay of a recorded [`Trace`] on an algebra `A` with values `V`.
+pub trait Replay<A, V> {
+    /// Result of the replay: either a value or a scalar of the algebra.
+    type Output;
+
+    /// Compute the output of the trace with the given values as inputs.
+    fn replay(&self, algebra: &mut A, inputs: &[V]) -> Result<Self::Output>;
+}
+
+/// A recorded operation. Arguments are indices of previous operations.
+/// * Array operations are only recorded for arrays (feature `arrayfire`).
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "arrayfire"), allow(dead_code))]
+enum Op<D: HasDims, C> {
+    Input,
+    Constant(D),
+    Variable(D),
+    Detach(usize),
+    Add(usize, usize),
+    Zeros(usize),
+    Ones(usize),
+    Neg(usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Exp(usize),
+    Log(usize),
+    Log1p(usize),
+    Sin(usize),
+    Cos(usize),
+    Tanh(usize),
+    Sigmoid(usize),
+    Reciprocal(usize),
+    Sqrt(usize),
+    Div(usize, usize),
+    Setc(usize, C),
+    Addc(usize, C),
+    Mulc(usize, C),
+    Powc(usize, C),
+    SelectArgmax(usize, usize, Option<usize>, Option<usize>),
+    Flat(usize),
+    Moddims(usize, D::Dims),
+    TileAs(usize, D::Dims),
+    SumAs(usize, D::Dims),
+    ConstantAs(usize, D::Dims),
+    AsScalar(usize),
+    Scale(usize, usize),
+    Dot(usize, usize),
+    Matmul(usize, usize, MatProp, MatProp),
+    Transpose(usize, bool),
+}
+
+/// A value or a scalar computed during a replay.
+#[cfg_attr(not(feature = "arrayfire"), allow(dead_code))]
+enum Slot<V, S> {
+    Value(V),
+    Scalar(S),
+}
+
+impl<D: HasDims, C> Op<D, C> {
+    /// Arguments of the operation, together with whether they are expected to be scalars.
+    fn arguments(&self) -> Vec<(usize, bool)> {
+        use Op::*;
+        match self {
+            Input | Constant(_) | Variable(_) => Vec::new(),
+            Detach(a)
+            | Zeros(a)
+            | Ones(a)
+            | Neg(a)
+            | Exp(a)
+            | Log(a)
+            | Log1p(a)
+            | Sin(a)
+            | Cos(a)
+            | Tanh(a)
+            | Sigmoid(a)
+            | Reciprocal(a)
+            | Sqrt(a)
+            | Setc(a, _)
+            | Addc(a, _)
+            | Mulc(a, _)
+            | Powc(a, _)
+            | Flat(a)
+            | Moddims(a, _)
+            | TileAs(a, _)
+            | SumAs(a, _)
+            | AsScalar(a)
+            | Transpose(a, _) => vec![(*a, false)],
+            Add(a, b) | Sub(a, b) | Mul(a, b) | Div(a, b) | Dot(a, b) | Matmul(a, b, _, _) => {
+                vec![(*a, false), (*b, false)]
+            }
+            SelectArgmax(a, b, r0, r1) => {
+                let mut args = vec![(*a, false), (*b, false)];
+                args.extend(r0.iter().chain(r1.iter()).map(|r| (*r, false)));
+                args
+            }
+            ConstantAs(s, _) => vec![(*s, true)],
+            Scale(s, a) => vec![(*s, true), (*a, false)],
+        }
+    }
+
+    /// Whether the result of the operation is a scalar.
+    fn is_scalar(&self) -> bool {
+        matches!(self, Op::AsScalar(_) | Op::Dot(_, _))
+    }
+}
+
+impl<V, S> Slot<V, S> {
+    // Kinds of arguments are checked by `Trace::record`.
+    fn value(&self) -> &V {
+        match self {
+            Slot::Value(v) => v,
+            Slot::Scalar(_) => unreachable!("expected a value"),
+        }
+    }
+
+    fn into_value(self) -> V {
+        match self {
+            Slot::Value(v) => v,
+            Slot::Scalar(_) => unreachable!("expected a value"),
+        }
+    }
+}
+
+impl Traced for TracedValue {
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn is_scalar() -> bool {
+        false
+    }
+}
+
+impl Traced for TracedScalar {
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn is_scalar() -> bool {
+        true
+    }
+}
+
+impl<D, C> std::fmt::Debug for Tracer<D, C>
+where
+    D: HasDims + std::fmt::Debug,
+    D::Dims: std::fmt::Debug,
+    C: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tracer").field("ops", &self.ops).finish()
+    }
+}
+
+impl<D, C, O> std::fmt::Debug for Trace<D, C, O>
+where
+    D: HasDims + std::fmt::Debug,
+    D::Dims: std::fmt::Debug,
+    C: std::fmt::Debug,
+    O: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trace")
+            .field("ops", &self.ops)
+            .field("num_inputs", &self.num_inputs)
+            .field("output", &self.output)
+            .finish()
+    }
+}
+
+impl<D, C, O> Clone for Trace<D, C, O>
+where
+    D: HasDims + Clone,
+    D::Dims: Clone,
+    C: Clone,
+    O: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            ops: self.ops.clone(),
+            num_inputs: self.num_inputs,
+            output: self.output.clone(),
+            values: Vec::new(),
+            gradients: Vec::new(),
+        }
+    }
+}
+
+impl<D: HasDims, C> Tracer<D, C> {
+    fn push(&mut self, op: Op<D, C>) -> usize {
+        self.ops.push(op);
+        self.ops.len() - 1
+    }
+
+    fn push_value(&mut self, op: Op<D, C>) -> TracedValue {
+        TracedValue(self.push(op))
+    }
+}
+
+impl<D: HasDims, C, O: Traced> Trace<D, C, O> {
+    /// Record the operations of the function `f` applied to `num_inputs` placeholder inputs.
+    /// * The closure `f` is typically a generic formula instantiated with the algebra [`Tracer`].
+    /// * The result is the trace of the single output of `f`, either a [`TracedValue`] or a
+    /// [`TracedScalar`].
+    pub fn record<F>(num_inputs: usize, f: F) -> Result<Self>
+    where
+        F: FnOnce(&mut Tracer<D, C>, &[TracedValue]) -> Result<O>,
+    {
+        let mut tracer = Tracer { ops: Vec::new() };
+        let inputs = (0..num_inputs)
+            .map(|_| tracer.push_value(Op::Input))
+            .collect::<Vec<_>>();
+        let output = f(&mut tracer, &inputs)?;
+        let ops = tracer.ops;
+        // Values of another tracer may refer to incorrect or future operations.
+        let is_valid = |(index, is_scalar): (usize, bool)| {
+            ops.get(index).map(Op::is_scalar) == Some(is_scalar)
+        };
+        let has_foreign_arguments = ops.iter().enumerate().any(|(i, op)| {
+            op.arguments()
+                .into_iter()
+                .any(|arg| arg.0 >= i || !is_valid(arg))
+        });
+        if has_foreign_arguments || !is_valid((output.index(), O::is_scalar())) {
+            return Err(Error::foreign_trace(func_name!()));
+        }
+        Ok(Self {
+            ops,
+            num_inputs,
+            output,
+            values: Vec::new(),
+            gradients: Vec::new(),
+        })
+    }
+
+    /// Number of inputs of the trace.
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    /// Number of recorded operations, including inputs.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether no operations were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Replay the operations on `algebra`. Array operations are delegated to `replay_array`.
+    fn replay_slots<A, V, S, F>(
+        &self,
+        algebra: &mut A,
+        inputs: &[V],
+        mut replay_array: F,
+    ) -> Result<Slot<V, S>>
+    where
+        A: CoreAlgebra<D, Value = V>
+            + ArithAlgebra<V>
+            + AnalyticAlgebra<V>
+            + ConstArithAlgebra<V, C>
+            + CompareAlgebra<V>,
+        F: FnMut(&mut A, &Op<D, C>, &[Slot<V, S>]) -> Result<Slot<V, S>>,
+        D: Clone,
+        C: Clone,
+        V: Clone,
+    {
+        check_equal_lengths(func_name!(), &[inputs.len(), self.num_inputs])?;
+        let mut slots = Vec::with_capacity(self.ops.len());
+        for op in &self.ops {
+            let s = &slots;
+            let v = |a: &usize| Slot::value(&s[*a]);
+            let value = match op {
+                Op::Input => inputs[s.len()].clone(),
+                Op::Constant(data) => algebra.constant(data.clone()),
+                Op::Variable(data) => algebra.variable(data.clone()),
+                Op::Detach(a) => algebra.detach(v(a)),
+                Op::Add(a, b) => algebra.add(v(a), v(b))?,
+                Op::Zeros(a) => algebra.zeros(v(a)),
+                Op::Ones(a) => algebra.ones(v(a)),
+                Op::Neg(a) => algebra.neg(v(a)),
+                Op::Sub(a, b) => algebra.sub(v(a), v(b))?,
+                Op::Mul(a, b) => algebra.mul(v(a), v(b))?,
+                Op::Exp(a) => algebra.exp(v(a)),
+                Op::Log(a) => algebra.log(v(a)),
+                Op::Log1p(a) => algebra.log1p(v(a)),
+                Op::Sin(a) => algebra.sin(v(a)),
+                Op::Cos(a) => algebra.cos(v(a)),
+                Op::Tanh(a) => algebra.tanh(v(a)),
+                Op::Sigmoid(a) => algebra.sigmoid(v(a)),
+                Op::Reciprocal(a) => algebra.reciprocal(v(a)),
+                Op::Sqrt(a) => algebra.sqrt(v(a)),
+                Op::Div(a, b) => algebra.div(v(a), v(b))?,
+                Op::Setc(a, c) => algebra.setc(v(a), c.clone()),
+                Op::Addc(a, c) => algebra.addc(v(a), c.clone()),
+                Op::Mulc(a, c) => algebra.mulc(v(a), c.clone()),
+                Op::Powc(a, c) => algebra.powc(v(a), c.clone()),
+                Op::SelectArgmax(a, b, r0, r1) => {
+                    algebra.select_argmax(v(a), v(b), r0.as_ref().map(v), r1.as_ref().map(v))?
+                }
+                _ => {
+                    let slot = replay_array(algebra, op, s)?;
+                    slots.push(slot);
+                    continue;
+                }
+            };
+            slots.push(Slot::Value(value));
+        }
+        Ok(slots.swap_remove(self.output.index()))
+    }
+}
+
+/// Traces of scalars only contain element-wise operations.
+impl<T, C, A, V> Replay<A, V> for Trace<T, C, TracedValue>
+where
+    A: CoreAlgebra<T, Value = V>
+        + ArithAlgebra<V>
+        + AnalyticAlgebra<V>
+        + ConstArithAlgebra<V, C>
+        + CompareAlgebra<V>,
+    T: Number,
+    C: Clone,
+    V: Clone,
+{
+    type Output = V;
+
+    fn replay(&self, algebra: &mut A, inputs: &[V]) -> Result<V> {
+        let slot = self.replay_slots::<_, _, (), _>(algebra, inputs, |_, _, _| {
+            unreachable!("array operations are not recorded for scalars")
+        })?;
+        Ok(slot.into_value())
+    }
+}
+
+impl<T: Number, C> Trace<T, C, TracedValue> {
+    /// Compute the output of the trace for the given inputs.
+    pub fn eval<E>(&mut self, eval: &mut E, inputs: &[T]) -> Result<T>
+    where
+        E: CoreAlgebra<T, Value = T>
+            + ArithAlgebra<T>
+            + AnalyticAlgebra<T>
+            + ConstArithAlgebra<T, C>
+            + CompareAlgebra<T>,
+        C: Clone,
+    {
+        check_equal_lengths(func_name!(), &[inputs.len(), self.num_inputs])?;
+        self.values.clear();
+        for op in &self.ops {
+            let values = &self.values;
+            let v = |a: &usize| &values[*a];
+            let value = match op {
+                Op::Input => inputs[values.len()],
+                Op::Constant(data) | Op::Variable(data) => *data,
+                Op::Detach(a) => *v(a),
+                Op::Add(a, b) => eval.add(v(a), v(b))?,
+                Op::Zeros(a) => eval.zeros(v(a)),
+                Op::Ones(a) => eval.ones(v(a)),
+                Op::Neg(a) => eval.neg(v(a)),
+                Op::Sub(a, b) => eval.sub(v(a), v(b))?,
+                Op::Mul(a, b) => eval.mul(v(a), v(b))?,
+                Op::Exp(a) => eval.exp(v(a)),
+                Op::Log(a) => eval.log(v(a)),
+                Op::Log1p(a) => eval.log1p(v(a)),
+                Op::Sin(a) => eval.sin(v(a)),
+                Op::Cos(a) => eval.cos(v(a)),
+                Op::Tanh(a) => eval.tanh(v(a)),
+                Op::Sigmoid(a) => eval.sigmoid(v(a)),
+                Op::Reciprocal(a) => eval.reciprocal(v(a)),
+                Op::Sqrt(a) => eval.sqrt(v(a)),
+                Op::Div(a, b) => eval.div(v(a), v(b))?,
+                Op::Setc(a, c) => eval.setc(v(a), c.clone()),
+                Op::Addc(a, c) => eval.addc(v(a), c.clone()),
+                Op::Mulc(a, c) => eval.mulc(v(a), c.clone()),
+                Op::Powc(a, c) => eval.powc(v(a), c.clone()),
+                Op::SelectArgmax(a, b, r0, r1) => {
+                    eval.select_argmax(v(a), v(b), r0.as_ref().map(v), r1.as_ref().map(v))?
+                }
+                _ => unreachable!("array operations are not recorded for scalars"),
+            };
+            self.values.push(value);
+        }
+        Ok(self.values[self.output.0])
+    }
+
+    /// Compute the output of the trace for the given inputs, then run backward propagation
+    /// from the output using the initial gradient value `direction`.
+    /// * The result contains the output and the gradients of the inputs. Inputs that do not
+    /// contribute to the output have no gradient.
+    /// * Backward propagation computes first-order gradients only.
+    pub fn eval_with_gradients<E>(
+        &mut self,
+        eval: &mut E,
+        inputs: &[T],
+        direction: T,
+    ) -> Result<(T, Vec<Option<T>>)>
+    where
+        E: CoreAlgebra<T, Value = T>
+            + ArithAlgebra<T>
+            + AnalyticAlgebra<T>
+            + ConstArithAlgebra<T, C>
+            + CompareAlgebra<T>,
+        C: std::ops::Sub<C, Output = C> + num::One + Clone,
+    {
+        let output = self.eval(eval, inputs)?;
+        self.gradients.clear();
+        self.gradients.resize(self.ops.len(), None);
+        self.gradients[self.output.0] = Some(direction);
+        for i in (self.num_inputs..=self.output.0).rev() {
+            let gradient = match self.gradients[i].take() {
+                Some(gradient) => gradient,
+                None => continue,
+            };
+            let values = &self.values;
+            let gradients = &mut self.gradients;
+            let mut add = |eval: &mut E, a: usize, g: T| -> Result<()> {
+                gradients[a] = Some(match gradients[a].take() {
+                    None => g,
+                    Some(x) => eval.add(&x, &g)?,
+                });
+                Ok(())
+            };
+            match &self.ops[i] {
+                Op::Input
+                | Op::Constant(_)
+                | Op::Variable(_)
+                | Op::Detach(_)
+                | Op::Zeros(_)
+                | Op::Ones(_)
+                | Op::Setc(_, _) => {}
+                Op::Add(a, b) => {
+                    add(eval, *a, gradient)?;
+                    add(eval, *b, gradient)?;
+                }
+                Op::Neg(a) => {
+                    let g = eval.neg(&gradient);
+                    add(eval, *a, g)?;
+                }
+                Op::Sub(a, b) => {
+                    let g = eval.neg(&gradient);
+                    add(eval, *b, g)?;
+                    add(eval, *a, gradient)?;
+                }
+                Op::Mul(a, b) => {
+                    let g0 = eval.mul(&gradient, &values[*b])?;
+                    let g1 = eval.mul(&values[*a], &gradient)?;
+                    add(eval, *a, g0)?;
+                    add(eval, *b, g1)?;
+                }
+                Op::Exp(a) => {
+                    let g = eval.mul(&gradient, &values[i])?;
+                    add(eval, *a, g)?;
+                }
+                Op::Log(a) => {
+                    let g = eval.div(&gradient, &values[*a])?;
+                    add(eval, *a, g)?;
+                }
+                Op::Log1p(a) => {
+                    let one = eval.ones(&values[*a]);
+                    let d = eval.add(&values[*a], &one)?;
+                    let g = eval.div(&gradient, &d)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Sin(a) => {
+                    let k = eval.cos(&values[*a]);
+                    let g = eval.mul(&gradient, &k)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Cos(a) => {
+                    let k = eval.sin(&values[*a]);
+                    let k = eval.neg(&k);
+                    let g = eval.mul(&gradient, &k)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Tanh(a) => {
+                    let one = eval.ones(&values[i]);
+                    let c = eval.mul(&values[i], &values[i])?;
+                    let k = eval.sub(&one, &c)?;
+                    let g = eval.mul(&gradient, &k)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Sigmoid(a) => {
+                    let one = eval.ones(&values[i]);
+                    let d = eval.sub(&one, &values[i])?;
+                    let k = eval.mul(&values[i], &d)?;
+                    let g = eval.mul(&gradient, &k)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Reciprocal(a) => {
+                    let c = eval.mul(&values[i], &values[i])?;
+                    let k = eval.neg(&c);
+                    let g = eval.mul(&gradient, &k)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Sqrt(a) => {
+                    let d = eval.add(&values[i], &values[i])?;
+                    let g = eval.div(&gradient, &d)?;
+                    add(eval, *a, g)?;
+                }
+                Op::Div(a, b) => {
+                    let g0 = eval.div(&gradient, &values[*b])?;
+                    let c = eval.mul(&g0, &values[i])?;
+                    let g1 = eval.neg(&c);
+                    add(eval, *b, g1)?;
+                    add(eval, *a, g0)?;
+                }
+                Op::Addc(a, _) => {
+                    add(eval, *a, gradient)?;
+                }
+                Op::Mulc(a, c) => {
+                    let g = eval.mulc(&gradient, c.clone());
+                    add(eval, *a, g)?;
+                }
+                Op::Powc(a, c) => {
+                    let k = eval.powc(&values[*a], c.clone() - C::one());
+                    let k = eval.mulc(&k, c.clone());
+                    let g = eval.mul(&k, &gradient)?;
+                    add(eval, *a, g)?;
+                }
+                Op::SelectArgmax(a, b, r0, r1) => {
+                    let (v0, v1) = (&values[*a], &values[*b]);
+                    if let Some(r) = r0 {
+                        let g = eval.select_argmax(v0, v1, Some(&gradient), None)?;
+                        add(eval, *r, g)?;
+                    }
+                    if let Some(r) = r1 {
+                        let g = eval.select_argmax(v0, v1, None, Some(&gradient))?;
+                        add(eval, *r, g)?;
+                    }
+                }
+                _ => unreachable!("array operations are not recorded for scalars"),
+            }
+        }
+        let gradients = self.gradients[..self.num_inputs]
+            .iter_mut()
+            .map(Option::take)
+            .collect();
+        Ok((output, gradients))
+    }
+}
+
+impl<D: HasDims, C> CoreAlgebra<D> for Tracer<D, C> {
+    type Value = TracedValue;
+
+    fn variable(&mut self, data: D) -> TracedValue {
+        self.push_value(Op::Variable(data))
+    }
+
+    fn constant(&mut self, data: D) -> TracedValue {
+        self.push_value(Op::Constant(data))
+    }
+
+    fn detach(&mut self, value: &TracedValue) -> TracedValue {
+        self.push_value(Op::Detach(value.0))
+    }
+
+    fn add(&mut self, v1: &TracedValue, v2: &TracedValue) -> Result<TracedValue> {
+        Ok(self.push_value(Op::Add(v1.0, v2.0)))
+    }
+}
+
+impl<D: HasDims, C> ArithAlgebra<TracedValue> for Tracer<D, C> {
+    fn zeros(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Zeros(v.0))
+    }
+
+    fn ones(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Ones(v.0))
+    }
+
+    fn neg(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Neg(v.0))
+    }
+
+    fn sub(&mut self, v0: &TracedValue, v1: &TracedValue) -> Result<TracedValue> {
+        Ok(self.push_value(Op::Sub(v0.0, v1.0)))
+    }
+
+    fn mul(&mut self, v0: &TracedValue, v1: &TracedValue) -> Result<TracedValue> {
+        Ok(self.push_value(Op::Mul(v0.0, v1.0)))
+    }
+}
+
+impl<D: HasDims, C> AnalyticAlgebra<TracedValue> for Tracer<D, C> {
+    fn exp(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Exp(v.0))
+    }
+
+    fn log(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Log(v.0))
+    }
+
+    fn log1p(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Log1p(v.0))
+    }
+
+    fn sin(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Sin(v.0))
+    }
+
+    fn cos(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Cos(v.0))
+    }
+
+    fn tanh(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Tanh(v.0))
+    }
+
+    fn sigmoid(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Sigmoid(v.0))
+    }
+
+    fn reciprocal(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Reciprocal(v.0))
+    }
+
+    fn sqrt(&mut self, v: &TracedValue) -> TracedValue {
+        self.push_value(Op::Sqrt(v.0))
+    }
+
+    fn div(&mut self, v0: &TracedValue, v1: &TracedValue) -> Result<TracedValue> {
+        Ok(self.push_value(Op::Div(v0.0, v1.0)))
+    }
+}
+
+impl<D: HasDims, C> ConstArithAlgebra<TracedValue, C> for Tracer<D, C> {
+    fn setc(&mut self, v: &TracedValue, c: C) -> TracedValue {
+        self.push_value(Op::Setc(v.0, c))
+    }
+
+    fn addc(&mut self, v: &TracedValue, c: C) -> TracedValue {
+        self.push_value(Op::Addc(v.0, c))
+    }
+
+    fn mulc(&mut self, v: &TracedValue, c: C) -> TracedValue {
+        self.push_value(Op::Mulc(v.0, c))
+    }
+
+    fn powc(&mut self, v: &TracedValue, c: C) -> TracedValue {
+        self.push_value(Op::Powc(v.0, c))
+    }
+}
+
+impl<D: HasDims, C> CompareAlgebra<TracedValue> for Tracer<D, C> {
+    fn select_argmax(
+        &mut self,
+        v0: &TracedValue,
+        v1: &TracedValue,
+        r0: Option<&TracedValue>,
+        r1: Option<&TracedValue>,
+    ) -> Result<TracedValue> {
+        Ok(self.push_value(Op::SelectArgmax(
+            v0.0,
+            v1.0,
+            r0.map(|r| r.0),
+            r1.map(|r| r.0),
+        )))
+    }
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_trace {
+    use super::*;
+    use crate::{array::ArrayAlgebra, arrayfire::Float, matrix::MatrixAlgebra};
+    use arrayfire as af;
+
+    impl<V, S> Slot<V, S> {
+        fn scalar(&self) -> &S {
+            match self {
+                Slot::Scalar(s) => s,
+                Slot::Value(_) => unreachable!("expected a scalar"),
+            }
+        }
+
+        fn into_scalar(self) -> S {
+            match self {
+                Slot::Scalar(s) => s,
+                Slot::Value(_) => unreachable!("expected a scalar"),
+            }
+        }
+    }
+
+    /// Replay an array operation.
+    fn replay_array<A, V, T, C>(
+        algebra: &mut A,
+        op: &Op<af::Array<T>, C>,
+        slots: &[Slot<V, A::Scalar>],
+    ) -> Result<Slot<V, A::Scalar>>
+    where
+        A: ArrayAlgebra<V, Dims = af::Dim4> + MatrixAlgebra<V>,
+        T: Float,
+    {
+        use Slot::{Scalar, Value};
+
+        let v = |a: &usize| slots[*a].value();
+        let slot = match op {
+            Op::Flat(a) => Value(algebra.flat(v(a))),
+            Op::Moddims(a, dims) => Value(algebra.moddims(v(a), *dims)?),
+            Op::TileAs(a, dims) => Value(algebra.tile_as(v(a), *dims)?),
+            Op::SumAs(a, dims) => Value(algebra.sum_as(v(a), *dims)?),
+            Op::ConstantAs(x, dims) => Value(algebra.constant_as(slots[*x].scalar(), *dims)),
+            Op::AsScalar(a) => Scalar(algebra.as_scalar(v(a))?),
+            Op::Scale(x, a) => Value(algebra.scale(slots[*x].scalar(), v(a))),
+            Op::Dot(a, b) => Scalar(algebra.dot(v(a), v(b))?),
+            Op::Matmul(a, b, prop1, prop2) => Value(algebra.matmul(v(a), v(b), *prop1, *prop2)?),
+            Op::Transpose(a, conjugate) => Value(algebra.transpose(v(a), *conjugate)?),
+            _ => unreachable!("element-wise operations are replayed by the trace"),
+        };
+        Ok(slot)
+    }
+
+    impl<T, C, A, V> Replay<A, V> for Trace<af::Array<T>, C, TracedValue>
+    where
+        A: CoreAlgebra<af::Array<T>, Value = V>
+            + ArithAlgebra<V>
+            + AnalyticAlgebra<V>
+            + ConstArithAlgebra<V, C>
+            + CompareAlgebra<V>
+            + ArrayAlgebra<V, Dims = af::Dim4>
+            + MatrixAlgebra<V>,
+        T: Float,
+        C: Clone,
+        V: Clone,
+    {
+        type Output = V;
+
+        fn replay(&self, algebra: &mut A, inputs: &[V]) -> Result<V> {
+            let slot = self.replay_slots(algebra, inputs, replay_array)?;
+            Ok(slot.into_value())
+        }
+    }
+
+    impl<T, C, A, V> Replay<A, V> for Trace<af::Array<T>, C, TracedScalar>
+    where
+        A: CoreAlgebra<af::Array<T>, Value = V>
+            + ArithAlgebra<V>
+            + AnalyticAlgebra<V>
+            + ConstArithAlgebra<V, C>
+            + CompareAlgebra<V>
+            + ArrayAlgebra<V, Dims = af::Dim4>
+            + MatrixAlgebra<V>,
+        T: Float,
+        C: Clone,
+        V: Clone,
+    {
+        type Output = A::Scalar;
+
+        fn replay(&self, algebra: &mut A, inputs: &[V]) -> Result<A::Scalar> {
+            let slot = self.replay_slots(algebra, inputs, replay_array)?;
+            Ok(slot.into_scalar())
+        }
+    }
+
+    impl<T: Float, C> ArrayAlgebra<TracedValue> for Tracer<af::Array<T>, C> {
+        type Dims = af::Dim4;
+        type Scalar = TracedScalar;
+
+        fn flat(&mut self, v: &TracedValue) -> TracedValue {
+            self.push_value(Op::Flat(v.0))
+        }
+
+        fn moddims(&mut self, v: &TracedValue, dims: af::Dim4) -> Result<TracedValue> {
+            Ok(self.push_value(Op::Moddims(v.0, dims)))
+        }
+
+        fn tile_as(&mut self, v: &TracedValue, dims: af::Dim4) -> Result<TracedValue> {
+            Ok(self.push_value(Op::TileAs(v.0, dims)))
+        }
+
+        fn sum_as(&mut self, v: &TracedValue, dims: af::Dim4) -> Result<TracedValue> {
+            Ok(self.push_value(Op::SumAs(v.0, dims)))
+        }
+
+        fn constant_as(&mut self, v: &TracedScalar, dims: af::Dim4) -> TracedValue {
+            self.push_value(Op::ConstantAs(v.0, dims))
+        }
+
+        fn as_scalar(&mut self, v: &TracedValue) -> Result<TracedScalar> {
+            Ok(TracedScalar(self.push(Op::AsScalar(v.0))))
+        }
+
+        fn scale(&mut self, lambda: &TracedScalar, v: &TracedValue) -> TracedValue {
+            self.push_value(Op::Scale(lambda.0, v.0))
+        }
+
+        fn dot(&mut self, v1: &TracedValue, v2: &TracedValue) -> Result<TracedScalar> {
+            Ok(TracedScalar(self.push(Op::Dot(v1.0, v2.0))))
+        }
+    }
+
+    impl<T: Float, C> MatrixAlgebra<TracedValue> for Tracer<af::Array<T>, C> {
+        fn matmul(
+            &mut self,
+            v1: &TracedValue,
+            v2: &TracedValue,
+            prop1: MatProp,
+            prop2: MatProp,
+        ) -> Result<TracedValue> {
+            Ok(self.push_value(Op::Matmul(v1.0, v2.0, prop1, prop2)))
+        }
+
+        fn transpose(&mut self, v: &TracedValue, conjugate: bool) -> Result<TracedValue> {
+            Ok(self.push_value(Op::Transpose(v.0, conjugate)))
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// Checkpointing performs exactly the same computations.
+#![allow(clippy::float_cmp)]
+
+use gad::{
+    net::{Checkpoint, Then},
+    prelude::*,
+};
+
+/// Compute `sin(w * x)` for a scalar weight `w`.
+#[derive(Clone)]
+struct SinLayer {
+    weight: f32,
+}
+
+impl SinLayer {
+    fn new(weight: f32) -> Self {
+        Self { weight }
+    }
+}
+
+impl<A, V> Net<A> for SinLayer
+where
+    A: HasGradientReader + CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V>,
+    V: HasGradientId,
+    A::GradientReader: GradientReader<V::GradientId, f32>,
+{
+    type Input = V;
+    type Output = V;
+    type Weights = f32;
+    type GradientInfo = V::GradientId;
+
+    fn eval_with_gradient_info(
+        &self,
+        g: &mut A,
+        input: Self::Input,
+    ) -> Result<(Self::Output, Self::GradientInfo)> {
+        let w = g.variable(self.weight);
+        let x = g.mul(&w, &input)?;
+        Ok((g.sin(&x), w.gid()?))
+    }
+
+    fn get_weights(&self) -> Self::Weights {
+        self.weight
+    }
+
+    fn update_weights(&mut self, delta: Self::Weights) -> Result<()> {
+        self.weight += delta;
+        Ok(())
+    }
+
+    fn set_weights(&mut self, weights: Self::Weights) -> Result<()> {
+        self.weight = weights;
+        Ok(())
+    }
+
+    fn read_weight_gradients(
+        &self,
+        info: Self::GradientInfo,
+        reader: &A::GradientReader,
+    ) -> Result<Self::Weights> {
+        reader
+            .read(info)
+            .cloned()
+            .ok_or_else(|| Error::missing_gradient(func_name!()))
+    }
+}
+
+/// Chain two networks that support several algebras.
+fn then<N1, N2>(net1: N1, net2: N2) -> Then<N1, N2>
+where
+    N1: Net<Graph1>,
+    N2: Net<Graph1, Input = N1::Output>,
+{
+    net1.then(net2)
+}
+
+/// Checkpoint a network that supports several algebras.
+fn checkpoint<N: Net<Graph1>>(net: N) -> Checkpoint<N> {
+    net.checkpoint()
+}
+
+/// Number of nodes created in a profiled graph.
+fn node_count(g: &Graph1) -> usize {
+    g.profile().unwrap().total().nodes
+}
+
+#[test]
+fn test_checkpoint() -> Result<()> {
+    let inner = then(SinLayer::new(0.5), SinLayer::new(1.5));
+    let plain = then(SinLayer::new(2.0), inner.clone());
+    let checkpointed = then(SinLayer::new(2.0), checkpoint(inner));
+
+    let mut g1 = Graph1::new();
+    g1.enable_profiling();
+    let x1 = g1.variable(0.3f32);
+    let (y1, info1) = plain.eval_with_gradient_info(&mut g1, x1.clone())?;
+    let mut g2 = Graph1::new();
+    g2.enable_profiling();
+    let x2 = g2.variable(0.3f32);
+    let (y2, info2) = checkpointed.eval_with_gradient_info(&mut g2, x2.clone())?;
+    assert_eq!(y1.data(), y2.data());
+    assert_eq!(checkpointed.evaluate(0.3)?, *y2.data());
+    // The checkpointed layers are replaced by a node and a slot for the weight gradients.
+    assert_eq!(node_count(&g1), 10);
+    assert!(node_count(&g2) <= 6);
+
+    let gradients1 = g1.evaluate_gradients_once(y1.gid()?, 1.0)?;
+    let gradients2 = g2.evaluate_gradients_once(y2.gid()?, 1.0)?;
+    assert_eq!(
+        gradients1.get(x1.gid()?).unwrap(),
+        gradients2.get(x2.gid()?).unwrap()
+    );
+    let w1 = Net::<Graph1>::read_weight_gradients(&plain, info1, &gradients1)?;
+    let w2 = Net::<Graph1>::read_weight_gradients(&checkpointed, info2, &gradients2)?;
+    assert_eq!(
+        bincode::serialize(&w1).unwrap(),
+        bincode::serialize(&w2).unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_constant_input() -> Result<()> {
+    let plain = InputData::<f32, Graph1>::new(())
+        .then(SinLayer::new(0.5))
+        .then(SinLayer::new(1.5));
+    let checkpointed = InputData::<f32, Graph1>::new(())
+        .then(checkpoint(then(SinLayer::new(0.5), SinLayer::new(1.5))));
+
+    let mut g1 = Graph1::new();
+    let (y1, info1) = plain.eval_with_gradient_info(&mut g1, 0.3)?;
+    let mut g2 = Graph1::new();
+    let (y2, info2) = checkpointed.eval_with_gradient_info(&mut g2, 0.3)?;
+    assert_eq!(y1.data(), y2.data());
+
+    let gradients1 = g1.evaluate_gradients_once(y1.gid()?, 1.0)?;
+    let gradients2 = g2.evaluate_gradients_once(y2.gid()?, 1.0)?;
+    let w1 = Net::<Graph1>::read_weight_gradients(&plain, info1, &gradients1)?;
+    let w2 = Net::<Graph1>::read_weight_gradients(&checkpointed, info2, &gradients2)?;
+    assert_eq!(
+        bincode::serialize(&w1).unwrap(),
+        bincode::serialize(&w2).unwrap()
+    );
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_checkpoint_parallel() -> Result<()> {
+    let plain = then(SinLayer::new(0.5), SinLayer::new(1.5));
+    let checkpointed = checkpoint(plain.clone());
+
+    let mut g1 = Graph1::new();
+    let x1 = g1.variable(0.3f32);
+    let (y1, info1) = plain.eval_with_gradient_info(&mut g1, x1)?;
+    let mut g2 = Graph1::new();
+    let x2 = g2.variable(0.3f32);
+    let (y2, info2) = checkpointed.eval_with_gradient_info(&mut g2, x2)?;
+
+    let gradients1 = g1.evaluate_gradients_parallel(y1.gid()?, 1.0)?;
+    let gradients2 = g2.evaluate_gradients_parallel(y2.gid()?, 1.0)?;
+    let w1 = Net::<Graph1>::read_weight_gradients(&plain, info1, &gradients1)?;
+    let w2 = Net::<Graph1>::read_weight_gradients(&checkpointed, info2, &gradients2)?;
+    assert_eq!(
+        bincode::serialize(&w1).unwrap(),
+        bincode::serialize(&w2).unwrap()
+    );
+    Ok(())
+}
@@ -3,6 +3,26 @@
 
 use gad::prelude::*;
 
+#[test]
+fn test_setc() -> Result<()> {
+    let mut g = Graph1::new();
+    let a = g.variable(2i32);
+    let b = g.setc(&a, 5u8);
+    assert_eq!(*b.data(), 5);
+    // The result is a constant.
+    assert!(b.id().is_none());
+    let c = g.mul(&a, &b)?;
+    let gradients = g.evaluate_gradients_once(c.gid()?, 1)?;
+    assert_eq!(*gradients.get(a.gid()?).unwrap(), 5);
+
+    let mut g = GraphN::new();
+    let a = g.variable(2i32);
+    let b = g.setc(&a, 5u8);
+    assert_eq!(*b.data(), 5);
+    assert!(b.id().is_none());
+    Ok(())
+}
+
 #[test]
 fn test_addc() -> Result<()> {
     let mut g = Graph1::new();
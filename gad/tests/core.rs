@@ -30,6 +30,55 @@ fn test_add_all() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[allow(clippy::many_single_char_names)]
+fn test_detach() -> Result<()> {
+    let mut g = Graph1::new();
+    let a = g.variable(3i32);
+    let b = g.detach(&a);
+    assert_eq!(*b.data(), 3);
+    assert!(b.id().is_none());
+    // d(a + a') / da = 1 when a' is a detached copy of a.
+    let c = g.add(&a, &b)?;
+    let gradients = g.evaluate_gradients_once(c.gid()?, 1i32)?;
+    assert_eq!(*gradients.get(a.gid()?).unwrap(), 1);
+
+    let mut f = Forward1::new();
+    let a = f.dual(3i32, 1)?;
+    assert_eq!(f.detach(&a).tangent(), None);
+    assert_eq!(CoreAlgebra::<i32>::detach(&mut Eval::default(), &3), 3);
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::many_single_char_names)]
+fn test_no_grad() -> Result<()> {
+    let mut g = Graph1::new();
+    let a = g.variable(3i32);
+    let (b, c) = g.no_grad(|g| -> Result<_> {
+        assert!(!g.is_recording());
+        let b = g.variable(2i32);
+        let c = g.add(&a, &b)?;
+        Ok((b, c))
+    })?;
+    assert!(g.is_recording());
+    assert!(b.id().is_none());
+    assert!(c.id().is_none());
+    assert_eq!(*c.data(), 5);
+
+    // Recording resumes after a panic.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        g.no_grad(|_| panic!("no_grad"));
+    }));
+    assert!(result.is_err());
+    assert!(g.is_recording());
+
+    let d = g.add(&a, &c)?;
+    let gradients = g.evaluate_gradients_once(d.gid()?, 1i32)?;
+    assert_eq!(*gradients.get(a.gid()?).unwrap(), 1);
+    Ok(())
+}
+
 #[cfg(feature = "arrayfire")]
 mod af_core_test {
 
@@ -0,0 +1,220 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "macros")]
+// Weights are copied or updated with exact values.
+#![allow(clippy::float_cmp)]
+
+use gad::prelude::*;
+
+/// The function `x -> a * x + b`.
+#[derive(Net)]
+#[net(algebra = Graph1, input = Value<f32>, output = Value<f32>)]
+struct Affine {
+    a: WeightData<f32, Graph1>,
+    b: WeightData<f32, Graph1>,
+}
+
+impl Affine {
+    fn new(a: f32, b: f32) -> Self {
+        Self {
+            a: WeightData::new(a),
+            b: WeightData::new(b),
+        }
+    }
+
+    fn forward(
+        &self,
+        g: &mut Graph1,
+        x: Value<f32>,
+        info: &mut <Self as Net<Graph1>>::GradientInfo,
+    ) -> Result<Value<f32>> {
+        let a = self.a.eval_into(g, (), &mut info.a)?;
+        let b = self.b.eval_into(g, (), &mut info.b)?;
+        Ok(gad_expr!(g, a * x + b))
+    }
+}
+
+/// Two affine functions composed with `sin`.
+#[derive(Net)]
+#[net(algebra = Graph1, input = f32, output = Value<f32>, forward = compose)]
+struct Composition {
+    first: Affine,
+    second: Affine,
+    #[net(skip)]
+    repeat_first: bool,
+}
+
+fn compose(
+    net: &Composition,
+    g: &mut Graph1,
+    x: f32,
+    info: &mut <Composition as Net<Graph1>>::GradientInfo,
+) -> Result<Value<f32>> {
+    let x = g.constant(x);
+    let y = net.first.eval_into(g, x, &mut info.first)?;
+    let y = g.sin(&y);
+    let y = if net.repeat_first {
+        net.first.eval_into(g, y, &mut info.first)?
+    } else {
+        y
+    };
+    net.second.eval_into(g, y, &mut info.second)
+}
+
+#[test]
+fn test_derive_net() -> Result<()> {
+    let (x, a1, b1, a2, b2) = (0.5f32, 2f32, 1f32, 3f32, -1f32);
+    let net = Composition {
+        first: Affine::new(a1, b1),
+        second: Affine::new(a2, b2),
+        repeat_first: false,
+    };
+    let mut g = Graph1::new();
+    let (y, info) = net.eval_with_gradient_info(&mut g, x)?;
+    let s = a1 * x + b1;
+    assert!((*y.data() - (a2 * s.sin() + b2)).abs() < 1e-6);
+
+    let store = g.evaluate_gradients_once(y.gid()?, 1.0)?;
+    let gradients = net.read_weight_gradients(info, &store)?;
+    assert!((gradients.first.a - a2 * s.cos() * x).abs() < 1e-6);
+    assert!((gradients.first.b - a2 * s.cos()).abs() < 1e-6);
+    assert!((gradients.second.a - s.sin()).abs() < 1e-6);
+    assert!((gradients.second.b - 1.0).abs() < 1e-6);
+    Ok(())
+}
+
+#[test]
+fn test_derive_net_weights() -> anyhow::Result<()> {
+    let mut net = Composition {
+        first: Affine::new(2.0, 1.0),
+        second: Affine::new(3.0, -1.0),
+        repeat_first: false,
+    };
+    let weights = net.get_weights();
+    assert_eq!(weights.first.a, 2.0);
+    assert_eq!(weights.second.b, -1.0);
+
+    net.update_weights(weights.clone())?;
+    let updated = net.get_weights();
+    assert_eq!(updated.first.b, 2.0);
+    assert_eq!(updated.second.a, 6.0);
+
+    // Derived weights are converted field by field (here, to the same precision).
+    let cast: CompositionWeights<AffineWeights<f32, f32>, _> = weights.cast_into();
+    assert_eq!(cast.first.a, 2.0);
+    net.set_weights(CastWeights::cast_from(&cast))?;
+    assert_eq!(net.get_weights().second.b, -1.0);
+
+    let bytes = bincode::serialize(&weights)?;
+    net.set_weights(bincode::deserialize(&bytes)?)?;
+    let restored = net.get_weights();
+    assert_eq!(restored.first.a, 2.0);
+    assert_eq!(restored.second.b, -1.0);
+    Ok(())
+}
+
+#[test]
+fn test_derive_net_errors() {
+    let net = Composition {
+        first: Affine::new(2.0, 1.0),
+        second: Affine::new(3.0, -1.0),
+        repeat_first: true,
+    };
+    let mut g = Graph1::new();
+    assert!(matches!(
+        net.eval(&mut g, 0.5),
+        Err(Error::RepeatedEvaluation { .. })
+    ));
+
+    // Sub-networks that were not evaluated have no gradients.
+    let mut info = CompositionWeights {
+        first: None,
+        second: None,
+    };
+    let x = g.constant(0.5);
+    let y = net.second.eval_into(&mut g, x, &mut info.second).unwrap();
+    let store = g.evaluate_gradients_once(y.gid().unwrap(), 1.0).unwrap();
+    assert!(matches!(
+        net.read_weight_gradients(info, &store),
+        Err(Error::MissingGradient { .. })
+    ));
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_derive_test {
+    use super::*;
+    use arrayfire as af;
+
+    /// A two-layer network `x -> tanh(x * w1) * w2`.
+    #[derive(Net)]
+    #[net(algebra = A, input = af::Array<T>, output = <A as AfAlgebra<T>>::Value)]
+    struct Mlp<A, T>
+    where
+        A: AfAlgebra<T>,
+        T: Float,
+    {
+        input: InputData<af::Array<T>, A>,
+        w1: WeightData<af::Array<T>, A>,
+        w2: WeightData<af::Array<T>, A>,
+    }
+
+    impl<A, T> Mlp<A, T>
+    where
+        A: AfAlgebra<T>,
+        T: Float,
+    {
+        fn new(n: u64) -> Self {
+            Self {
+                input: InputData::new(af::dim4!(n, n)),
+                w1: WeightData::new(af::randn!(T; n, n)),
+                w2: WeightData::new(af::randn!(T; n, n)),
+            }
+        }
+
+        fn forward(
+            &self,
+            g: &mut A,
+            input: af::Array<T>,
+            info: &mut <Self as Net<A>>::GradientInfo,
+        ) -> Result<<A as AfAlgebra<T>>::Value> {
+            let x = self.input.eval_into(g, input, &mut info.input)?;
+            let w1 = self.w1.eval_into(g, (), &mut info.w1)?;
+            let w2 = self.w2.eval_into(g, (), &mut info.w2)?;
+            let h = g.matmul_nn(&x, &w1)?;
+            let h = g.tanh(&h);
+            g.matmul_nn(&h, &w2)
+        }
+    }
+
+    #[test]
+    fn test_derive_net_training() -> anyhow::Result<()> {
+        let mut train = Mlp::new(3).add_square_loss();
+        let a = af::Array::<f32>::new(
+            &[1.0, 2.0, 1.0, 1.0, 0.0, 1.0, 0.0, -2.0, -1.0],
+            af::dim4!(3, 3),
+        );
+        let target = af::Array::<f32>::new(
+            &[0.5, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.5],
+            af::dim4!(3, 3),
+        );
+        let samples = vec![(a.clone(), target.clone())];
+        let initial_loss = train.apply_gradient_step(0.0, samples.clone())?;
+        let mut loss = initial_loss;
+        for _ in 0..100 {
+            loss = train.apply_gradient_step(-0.01, samples.clone())?;
+            assert!(loss.is_finite());
+        }
+        assert!(loss < initial_loss);
+
+        let bytes = bincode::serialize(&train.get_weights())?;
+        let mut net = Mlp::new(3);
+        net.set_weights(bincode::deserialize(&bytes)?)?;
+        assert_eq!(net.check(a)?, target.dims());
+        assert_eq!(
+            bincode::serialize(&train.get_weights().w1)?,
+            bincode::serialize(&net.get_weights().w1)?
+        );
+        Ok(())
+    }
+}
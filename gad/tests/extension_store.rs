@@ -0,0 +1,90 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! User-defined operators that work with any gradient store.
+
+use gad::{
+    prelude::*,
+    store::{DenseGradientMap1, DenseGradientMapN},
+};
+
+pub trait UserAlgebra<Value> {
+    fn square(&mut self, v: &Value) -> Result<Value>;
+}
+
+impl UserAlgebra<f32> for Eval {
+    #[inline]
+    fn square(&mut self, v: &f32) -> Result<f32> {
+        Ok(v * v)
+    }
+}
+
+macro_rules! impl_graph {
+    ($config:ident, $store:ident) => {
+        impl<D, E, S, Dims> UserAlgebra<Value<D>> for Graph<$config<E, S>>
+        where
+            E: Default
+                + Clone
+                + CoreAlgebra<D, Value = D>
+                + UserAlgebra<D>
+                + ArithAlgebra<D>
+                + LinkedAlgebra<Value<D>, D>,
+            S: $store<D>,
+            D: HasDims<Dims = Dims> + Clone + 'static + Send + Sync,
+            Dims: PartialEq + std::fmt::Debug + Clone + 'static + Send + Sync,
+        {
+            fn square(&mut self, v: &Value<D>) -> Result<Value<D>> {
+                let result = self.eval().square(v.data())?;
+                let value = self.make_node(result, vec![v.input()], {
+                    let v = v.clone();
+                    move |graph, store, gradient| {
+                        if let Some(id) = v.id() {
+                            let c = graph.link(&v);
+                            let grad = graph.mul(&gradient, c)?;
+                            let grad = graph.add(&grad, &grad)?;
+                            store.add_gradient(graph, id, &grad)?;
+                        }
+                        Ok(())
+                    }
+                });
+                Ok(value)
+            }
+        }
+    };
+}
+
+impl_graph!(Config1, GradientStore1);
+impl_graph!(ConfigN, GradientStoreN);
+
+#[test]
+fn test_square_default_store() -> Result<()> {
+    let mut g = Graph1::new();
+    let a = g.variable(3.0f32);
+    let b = g.square(&a)?;
+    assert!((*b.data() - 9.0).abs() < 0.0001);
+    let gradients = g.evaluate_gradients_once(b.gid()?, 1.0)?;
+    assert!((*gradients.get(a.gid()?).unwrap() - 6.0).abs() < 0.0001);
+    Ok(())
+}
+
+#[test]
+fn test_square_dense_store() -> Result<()> {
+    let mut g = Graph::<Config1<Eval, DenseGradientMap1<f32>>>::new();
+    let a = g.variable(3.0f32);
+    let b = g.square(&a)?;
+    let gradients = g.evaluate_gradients_once(b.gid()?, 1.0)?;
+    assert!((*gradients.get(a.gid()?).unwrap() - 6.0).abs() < 0.0001);
+
+    let mut g = Graph::<ConfigN<Eval, DenseGradientMapN<f32>>>::new();
+    let a = g.variable(3.0f32);
+    let b = g.square(&a)?;
+    let one = g.constant(1.0f32);
+    let gradients = g.compute_gradients(b.gid()?, one)?;
+    let da = gradients.get(a.gid()?).unwrap().clone();
+    assert!((*da.data() - 6.0).abs() < 0.0001);
+    // Second-order differentials go through the same store.
+    let one = g.constant(1.0f32);
+    let gradients = g.compute_gradients(da.gid()?, one)?;
+    assert!((*gradients.get(a.gid()?).unwrap().data() - 2.0).abs() < 0.0001);
+    Ok(())
+}
@@ -0,0 +1,163 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![allow(clippy::many_single_char_names)]
+
+use gad::prelude::*;
+
+#[inline]
+fn assert_near(x: f32, y: f32) {
+    assert!((x - y).abs() < 0.001);
+}
+
+fn get_value<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+where
+    A: CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V>,
+{
+    // sin(x) * y + exp(x / y)
+    let s = g.sin(x);
+    let a = g.mul(&s, y)?;
+    let d = g.div(x, y)?;
+    let b = g.exp(&d);
+    g.add(&a, &b)
+}
+
+#[test]
+fn test_forward_vs_backward() -> Result<()> {
+    let mut g = Graph1::new();
+    let x = g.variable(0.7f32);
+    let y = g.variable(1.3f32);
+    let z = get_value(&mut g, &x, &y)?;
+    let gradients = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+
+    let mut f = Forward1::new();
+    let x0 = f.dual(0.7f32, 1.0)?;
+    let y0 = f.constant(1.3f32);
+    let z0 = get_value(&mut f, &x0, &y0)?;
+    assert_near(*z0.data(), *z.data());
+    assert_near(*z0.tangent().unwrap(), *gradients.get(x.gid()?).unwrap());
+
+    let x1 = f.constant(0.7f32);
+    let y1 = f.dual(1.3f32, 1.0)?;
+    let z1 = get_value(&mut f, &x1, &y1)?;
+    assert_near(*z1.tangent().unwrap(), *gradients.get(y.gid()?).unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_arith() -> Result<()> {
+    let mut g = Forward1::new();
+    let a = g.dual(1i32, 1)?;
+    let b = g.dual(2i32, 3)?;
+    let c = g.sub(&a, &b)?;
+    assert_eq!(*c.data(), -1);
+    assert_eq!(*c.tangent().unwrap(), -2);
+    let d = g.mul(&a, &b)?;
+    assert_eq!(*d.data(), 2);
+    assert_eq!(*d.tangent().unwrap(), 5);
+    let e = g.neg(&d);
+    assert_eq!(*e.tangent().unwrap(), -5);
+    Ok(())
+}
+
+#[test]
+fn test_constant() -> Result<()> {
+    let mut g = Forward1::new();
+    let a = g.variable(1i32);
+    let b = g.constant(2i32);
+    let c = g.mul(&a, &b)?;
+    assert_eq!(*c.data(), 2);
+    assert_eq!(c.tangent(), None);
+    Ok(())
+}
+
+#[test]
+fn test_analytic() -> Result<()> {
+    let mut g = Forward1::new();
+    let a = g.dual(0.5f32, 1.0)?;
+    let b = g.log1p(&a);
+    assert_near(*b.tangent().unwrap(), 1.0 / 1.5);
+    let b = g.tanh(&a);
+    assert_near(*b.tangent().unwrap(), 1.0 - 0.5f32.tanh().powi(2));
+    let b = g.sigmoid(&a);
+    let s = 1.0 / (1.0 + (-0.5f32).exp());
+    assert_near(*b.tangent().unwrap(), s * (1.0 - s));
+    let b = g.reciprocal(&a);
+    assert_near(*b.tangent().unwrap(), -4.0);
+    let b = g.sqrt(&a);
+    assert_near(*b.tangent().unwrap(), 0.5 / 0.5f32.sqrt());
+    let p = g.constant(3.0f32);
+    let b = g.pow(&a, &p)?;
+    assert_near(*b.tangent().unwrap(), 0.75);
+    Ok(())
+}
+
+#[test]
+fn test_const_arith() -> Result<()> {
+    let mut g = Forward1::new();
+    let a = g.dual(3i32, 2)?;
+    let b = g.addc(&a, 1u8);
+    assert_eq!(*b.data(), 4);
+    assert_eq!(*b.tangent().unwrap(), 2);
+    let b = g.mulc(&a, 2u8);
+    assert_eq!(*b.tangent().unwrap(), 4);
+    let b = g.powc(&a, 2u8);
+    assert_eq!(*b.data(), 9);
+    assert_eq!(*b.tangent().unwrap(), 12);
+    let b = g.setc(&a, 2u8);
+    assert_eq!(b.tangent(), None);
+    Ok(())
+}
+
+#[test]
+fn test_compare() -> Result<()> {
+    let mut g = Forward1::new();
+    let a = g.dual(1i32, 1)?;
+    let b = g.dual(2i32, 3)?;
+    let c = g.max(&a, &b)?;
+    assert_eq!(*c.data(), 2);
+    assert_eq!(*c.tangent().unwrap(), 3);
+    let c = g.min(&a, &b)?;
+    assert_eq!(*c.tangent().unwrap(), 1);
+    let n = g.neg(&a);
+    let c = g.abs(&n);
+    assert_eq!(*c.data(), 1);
+    assert_eq!(*c.tangent().unwrap(), 1);
+    let c = g.relu(&n);
+    assert_eq!(*c.tangent().unwrap(), 0);
+    Ok(())
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_forward_test {
+    use super::*;
+    use arrayfire as af;
+
+    #[test]
+    fn test_matmul() -> Result<()> {
+        let mut g = Forward1::new();
+        let a = af::randu::<f32>(af::dim4!(4, 3));
+        let b = af::randu::<f32>(af::dim4!(3, 5));
+        let direction = af::randu::<f32>(af::dim4!(4, 3));
+        let x = g.dual(a, direction.clone())?;
+        let y = g.constant(b.clone());
+        let z = g.matmul_nn(&x, &y)?;
+        let expected = af::matmul(&direction, &b, af::MatProp::NONE, af::MatProp::NONE);
+        testing::assert_almost_all_equal(z.tangent().unwrap(), &expected, 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot() -> Result<()> {
+        let mut g = Forward1::new();
+        let a = af::randu::<f32>(af::dim4!(4, 3));
+        let direction = af::randu::<f32>(af::dim4!(4, 3));
+        let x = g.dual(a.clone(), direction.clone())?;
+        let y = g.norm2(&x);
+        let z = g.dot(&x, &x)?;
+        let expected = 2.0 * af::sum_all(&(&a * &direction)).0;
+        assert_near(*y.tangent().unwrap(), expected);
+        assert_near(*z.tangent().unwrap(), expected);
+        Ok(())
+    }
+}
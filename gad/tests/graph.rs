@@ -68,6 +68,592 @@ fn test_hessian_and_more() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_vjp_and_jvp() -> Result<()> {
+    let mut g = Graph1::new();
+
+    let a = g.variable(3i32);
+    let b = g.variable(2i32);
+    let c = g.mul(&a, &b)?;
+    let d = g.sub(&a, &b)?;
+    let e = g.constant(1i32);
+
+    let (a, b, c, d) = (a.gid()?, b.gid()?, c.gid()?, d.gid()?);
+    // [dc/da, dc/db] = [2, 3] and [dd/da, dd/db] = [1, -1]
+    let grads = g.vjp(&[c, d], vec![1, 10], &[a, b])?;
+    assert_eq!(grads, vec![Some(12), Some(-7)]);
+    // Seeds on the same output are summed.
+    let grads = g.vjp(&[c, c], vec![1, 10], &[a, b])?;
+    assert_eq!(grads, vec![Some(22), Some(33)]);
+    assert!(g.vjp(&[c, d], vec![1], &[a, b]).is_err());
+
+    let products = g.reverse_jvp(&[a, b], vec![1, 10], &[c, d])?;
+    assert_eq!(products, vec![32, -9]);
+
+    let f = g.add(&e, &e)?;
+    assert_eq!(f.id(), None);
+    Ok(())
+}
+
+#[test]
+fn test_vjp_and_jvp_higher_order() -> Result<()> {
+    let mut g = GraphN::new();
+
+    let x = g.variable(1.0f32);
+    let y = g.variable(0.4f32);
+    // z = x * y^2, w = x + y
+    let z = {
+        let h = g.mul(&x, &y)?;
+        g.mul(&h, &y)?
+    };
+    let w = g.add(&x, &y)?;
+    let (x, y) = (x.gid()?, y.gid()?);
+
+    let s = g.constant(1f32);
+    let t = g.constant(2f32);
+    let grads = g.vjp(&[z.gid()?, w.gid()?], vec![s, t], &[x, y])?;
+    let dx = grads[0].as_ref().unwrap();
+    let dy = grads[1].as_ref().unwrap();
+    assert!((*dx.data() - 2.16).abs() < 0.0001); // y^2 + 2
+    assert!((*dy.data() - 2.8).abs() < 0.0001); // 2xy + 2
+
+    // Differentiate the gradient w.r.t. x again.
+    let s = g.constant(1f32);
+    let grads = g.vjp(&[dx.gid()?], vec![s], &[x, y])?;
+    assert_eq!(grads[0], None);
+    assert!((*grads[1].as_ref().unwrap().data() - 0.8).abs() < 0.0001); // 2y
+
+    let s = g.constant(1f32);
+    let t = g.constant(10f32);
+    let products = g.jvp(&[x, y], vec![s, t], &[z, w])?;
+    let dz = products[0].as_ref().unwrap();
+    let dw = products[1].as_ref().unwrap();
+    assert!((*dz.data() - 8.16).abs() < 0.0001); // y^2 + 20xy
+    assert!((*dw.data() - 11.0).abs() < 0.0001);
+
+    // The products can be differentiated again.
+    let s = g.constant(1f32);
+    let grads = g.vjp(&[dz.gid()?], vec![s], &[x, y])?;
+    assert!((*grads[0].as_ref().unwrap().data() - 8.0).abs() < 0.0001); // 20y
+    assert!((*grads[1].as_ref().unwrap().data() - 20.8).abs() < 0.0001); // 2y + 20x
+    Ok(())
+}
+
+#[test]
+fn test_dense_gradient_store() -> Result<()> {
+    use gad::store::{DenseGradientMap1, DenseGradientMapN};
+
+    let mut g = Graph::<Config1<Eval, DenseGradientMap1<f32>>>::new();
+    let x = g.variable(1.0f32);
+    let y = g.variable(0.4f32);
+    let z = {
+        let h = g.mul(&x, &y)?;
+        let h = g.mul(&h, &y)?;
+        g.exp(&h)
+    };
+    let store = g.evaluate_gradients(z.gid()?, 1.0)?;
+    let expected = 0.16f32.exp();
+    assert!((store.get(x.gid()?).unwrap() - 0.16 * expected).abs() < 0.0001);
+    assert!((store.get(y.gid()?).unwrap() - 0.8 * expected).abs() < 0.0001);
+    let grads = g.vjp(&[z.gid()?], vec![2.0], &[y.gid()?])?;
+    assert!((grads[0].unwrap() - 1.6 * expected).abs() < 0.0001);
+
+    // Nodes of another graph have no gradient in the store.
+    let mut g2 = Graph::<Config1<Eval, DenseGradientMap1<f32>>>::new();
+    let x2 = g2.variable(1.0f32);
+    assert_eq!(store.get(x2.gid()?), None);
+    assert!(matches!(
+        store.try_get(x2.gid()?),
+        Err(Error::ForeignNode { .. })
+    ));
+    let mut store = store;
+    assert!(store
+        .add_gradient::<f32, _>(&mut Eval::default(), x2.gid()?, &1.0)
+        .is_err());
+    // Inserting a gradient for a foreign node is ignored.
+    store.insert(x2.gid()?, 1.0);
+    assert_eq!(store.get(x2.gid()?), None);
+    assert!((store.get(x.gid()?).unwrap() - 0.16 * expected).abs() < 0.0001);
+
+    let mut g = Graph::<ConfigN<Eval, DenseGradientMapN<f32>>>::new();
+    let x = g.variable(1.0f32);
+    let y = g.variable(0.4f32);
+    let z = {
+        let h = g.mul(&x, &y)?;
+        g.mul(&h, &y)?
+    };
+    let one = g.constant(1.0f32);
+    let store = g.compute_gradients(z.gid()?, one)?;
+    let dz_dx = store.get(x.gid()?).unwrap().clone();
+    let one = g.constant(1.0f32);
+    let store = g.compute_gradients(dz_dx.gid()?, one)?;
+    assert!((*store.get(y.gid()?).unwrap().data() - 0.8).abs() < 0.0001); // 2y
+    assert_eq!(store.get(x.gid()?), None);
+    Ok(())
+}
+
+#[test]
+fn test_gradient_type() -> Result<()> {
+    // Copies of a graph share the same node ids.
+    let mut g1 = Graph1::new();
+    let x1 = g1.variable(1.0f32);
+    let mut g2 = g1.clone();
+    let y1 = g1.exp(&x1);
+    let y2 = g2.variable(1.0f64);
+
+    let mut store = g1.evaluate_gradients(y1.gid()?, 1.0)?;
+    assert_eq!(store.try_get(x1.gid()?)?, Some(&1.0f32.exp()));
+    match store.try_get(y2.gid()?) {
+        Err(Error::GradientType {
+            expected, actual, ..
+        }) => {
+            assert_eq!(expected, "f64");
+            assert_eq!(actual, "f32");
+        }
+        _ => panic!("expected a type error"),
+    }
+    let mut eval = Eval::default();
+    assert!(matches!(
+        store.add_gradient::<f64, _>(&mut eval, y2.gid()?, &1.0),
+        Err(Error::GradientType { .. })
+    ));
+
+    // Seeding the wrong node is reported during backward propagation.
+    assert!(matches!(
+        g1.evaluate_gradients(y2.gid()?, 1.0),
+        Err(Error::GradientType { .. })
+    ));
+
+    let mut g1 = GraphN::new();
+    let x1 = g1.variable(1.0f32);
+    let mut g2 = g1.clone();
+    let y1 = g1.mul(&x1, &x1)?;
+    let y2 = g2.variable(1.0f64);
+    let one = g1.constant(1.0f32);
+    let store = g1.compute_gradients(y1.gid()?, one)?;
+    assert!(matches!(
+        GradientReader::<_, f64>::try_read(&store, y2.gid()?),
+        Err(Error::GradientType { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_foreign_node() -> Result<()> {
+    let mut g1 = Graph1::new();
+    let mut g2 = Graph1::new();
+    let x1 = g1.variable(1.0f32);
+    let y1 = g1.exp(&x1);
+    let x2 = g2.variable(2.0f32);
+
+    // Ids of another graph are rejected by gradient computations.
+    assert!(matches!(
+        g2.evaluate_gradients(y1.gid()?, 1.0),
+        Err(Error::ForeignNode { .. })
+    ));
+    assert!(matches!(
+        g1.vjp(&[y1.gid()?], vec![1.0], &[x2.gid()?]),
+        Err(Error::ForeignNode { .. })
+    ));
+    assert!(matches!(
+        g1.evaluate_gradients_wrt(y1.gid()?, 1.0, &[x2.gid()?]),
+        Err(Error::ForeignNode { .. })
+    ));
+    assert!(matches!(
+        g2.register_hook(x1.gid()?, |_, _: &mut f32| Ok(())),
+        Err(Error::ForeignNode { .. })
+    ));
+
+    // Values of another graph are not wired in. The error is reported by the gradient
+    // computations that reach the result.
+    let z = g1.mul(&y1, &x2)?;
+    assert!((*z.data() - 2.0 * 1.0f32.exp()).abs() < 1e-6);
+    let w = g1.exp(&z);
+    match g1.evaluate_gradients(w.gid()?, 1.0) {
+        Err(Error::ForeignNode { name, node, .. }) => {
+            assert_eq!(name, "mul");
+            assert_eq!(node, x2.input());
+        }
+        _ => panic!("expected a foreign node"),
+    }
+    assert!(g1.evaluate_gradients(y1.gid()?, 1.0).is_ok());
+    assert!(g2.evaluate_gradients(x2.gid()?, 1.0).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_gradient_many() -> Result<()> {
+    let mut g = Graph1::new();
+
+    let a = g.variable(3i32);
+    let b = g.variable(2i32);
+    let c = g.mul(&a, &b)?;
+    let d = g.sub(&a, &b)?;
+    let (a, b, c, d) = (a.gid()?, b.gid()?, c.gid()?, d.gid()?);
+
+    let store = g.evaluate_gradients_many(vec![(c, 1), (d, 2), (c, 1)])?;
+    assert_eq!(*store.get(a).unwrap(), 6);
+    assert_eq!(*store.get(b).unwrap(), 4);
+    assert_eq!(*store.get(c).unwrap(), 2);
+
+    let store = g.evaluate_gradients_once_many(vec![(d, 1), (c, 1)])?;
+    assert_eq!(*store.get(a).unwrap(), 3);
+    assert_eq!(*store.get(b).unwrap(), 2);
+
+    let mut g = GraphN::new();
+    let a = g.variable(3i32);
+    let b = g.variable(2i32);
+    let c = g.mul(&a, &b)?;
+    let d = g.mul(&a, &a)?;
+    let (a, b) = (a.gid()?, b.gid()?);
+    let one = g.constant(1i32);
+    let store = g.compute_gradients_many(vec![(c.gid()?, one.clone()), (d.gid()?, one)])?;
+    assert_eq!(*store.get(a).unwrap().data(), 8);
+    assert_eq!(*store.get(b).unwrap().data(), 3);
+
+    let da = store.get(a).unwrap().gid()?;
+    let one = g.constant(1i32);
+    let store = g.compute_gradients_many(vec![(da, one)])?;
+    assert_eq!(*store.get(a).unwrap().data(), 2);
+    assert_eq!(*store.get(b).unwrap().data(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_gradient_wrt() -> Result<()> {
+    let mut g = Graph1::new();
+
+    let a = g.variable(3i32);
+    let b = g.variable(2i32);
+    let c = g.variable(5i32);
+    let d = g.mul(&a, &b)?;
+    let e = g.add(&d, &c)?;
+    let f = g.mul(&e, &c)?;
+    let h = g.variable(1i32);
+
+    let all = g.evaluate_gradients(f.gid()?, 1)?;
+    let some = g.evaluate_gradients_wrt(f.gid()?, 1, &[a.gid()?, e.gid()?])?;
+    assert_eq!(some.get(a.gid()?), all.get(a.gid()?));
+    assert_eq!(some.get(e.gid()?), all.get(e.gid()?));
+    assert_eq!(*some.get(a.gid()?).unwrap(), 10);
+    // Intermediate and unrelated gradients are not kept.
+    assert_eq!(some.get(b.gid()?), None);
+    assert_eq!(some.get(c.gid()?), None);
+    assert_eq!(some.get(d.gid()?), None);
+    assert_eq!(some.get(f.gid()?), None);
+
+    let none = g.evaluate_gradients_wrt(f.gid()?, 1, &[h.gid()?])?;
+    assert_eq!(none.get(h.gid()?), None);
+    assert_eq!(none.get(a.gid()?), None);
+
+    let itself = g.evaluate_gradients_wrt(f.gid()?, 1, &[f.gid()?])?;
+    assert_eq!(itself.get(f.gid()?), Some(&1));
+    Ok(())
+}
+
+#[test]
+fn test_hessian_vector_product() -> Result<()> {
+    let mut g = GraphN::new();
+
+    let x = g.variable(1.0f32);
+    let y = g.variable(0.4f32);
+    let c = g.variable(3.0f32);
+    // z = x * y^2 + c
+    let z = {
+        let h = g.mul(&x, &y)?;
+        let h = g.mul(&h, &y)?;
+        g.add(&h, &c)?
+    };
+    let (x, y, c) = (x.gid()?, y.gid()?, c.gid()?);
+
+    // H = [[0, 2y, 0], [2y, 2x, 0], [0, 0, 0]]
+    let u = g.constant(1f32);
+    let v = g.constant(10f32);
+    let w = g.constant(100f32);
+    let products = g.hessian_vector_product(z.gid()?, &[x, y, c], vec![u, v, w])?;
+    assert!((*products[0].as_ref().unwrap().data() - 8.0).abs() < 0.0001); // 20y
+    assert!((*products[1].as_ref().unwrap().data() - 20.8).abs() < 0.0001); // 2y + 20x
+    assert_eq!(products[2], None);
+
+    // Products are differentiable.
+    let s = g.constant(1f32);
+    let grads = g.compute_gradients(products[1].as_ref().unwrap().gid()?, s)?;
+    assert!((*grads.get(x).unwrap().data() - 20.0).abs() < 0.0001);
+    assert!((*grads.get(y).unwrap().data() - 2.0).abs() < 0.0001);
+    Ok(())
+}
+
+#[test]
+fn test_node_info() -> Result<()> {
+    let mut g = Graph1::new();
+    let x = g.variable(2.0f32);
+    let c = g.constant(3.0f32);
+    let y = g.mul(&x, &c)?;
+    let z = g.exp(&y);
+    g.label(&y, "scaled");
+
+    let info = g.node_info(x.input().unwrap()).unwrap();
+    assert_eq!(info.op(), "variable");
+    assert_eq!(info.type_name(), "f32");
+    assert_eq!(info.label(), None);
+    let info = g.node_info(y.input().unwrap()).unwrap();
+    assert_eq!(info.op(), "mul");
+    assert_eq!(format!("{:?}", info.dims()), "()");
+    assert_eq!(info.label(), Some("scaled"));
+    assert_eq!(g.node_info(z.input().unwrap()).unwrap().op(), "exp");
+    assert!(c.input().is_none());
+
+    // User-defined operators may be named or not.
+    let data = *z.data();
+    let u = g.make_node(data, vec![z.input()], |_, _, _| Ok(()));
+    assert_eq!(g.node_info(u.input().unwrap()).unwrap().op(), "custom");
+    let u = g.make_named_node("user", data, vec![u.input()], 0, |_, _, _| Ok(()));
+    assert_eq!(g.node_info(u.input().unwrap()).unwrap().op(), "user");
+
+    let debug = format!("{:?}", g);
+    assert!(debug.contains("mul () \"scaled\""));
+    assert!(debug.contains("exp ()"));
+    Ok(())
+}
+
+#[test]
+fn test_to_dot() -> Result<()> {
+    let mut g = Graph1::new();
+    let x = g.variable(2.0f32);
+    let c = g.constant(3.0f32);
+    let y = g.mul(&x, &c)?;
+    g.label(&y, "scaled");
+    let z = g.exp(&y);
+
+    let dot = g.to_dot();
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains("n0 [label=\"variable\\nf32 ()\", shape=box, color=black];"));
+    assert!(dot.contains("n1 [label=\"mul \\\"scaled\\\"\\nf32 ()\", shape=ellipse"));
+    assert!(dot.contains("n0 -> n1;"));
+    assert!(dot.contains("c1_1 -> n1;"));
+    assert!(dot.contains("n1 -> n2;"));
+
+    let gradients = g.evaluate_gradients(z.gid()?, 1.0)?;
+    let dot = g.to_dot_with_gradients(&gradients);
+    assert!(dot.contains("|grad| = 1e0\", shape=ellipse, color=black];"));
+    assert!(dot.contains("|grad| = 1.2102")); // 3 exp(6)
+
+    // Nodes after `y` have no gradient.
+    let gradients = g.evaluate_gradients(y.gid()?, f32::NAN)?;
+    let dot = g.to_dot_with_gradients(&gradients);
+    assert!(dot.contains("|grad| = NaN\", shape=box, color=red];"));
+    assert!(dot.contains("n2 [label=\"exp\\nf32 ()\", shape=ellipse, color=gray];"));
+    Ok(())
+}
+
+#[test]
+fn test_profile() -> Result<()> {
+    let mut g = Graph1::new();
+    assert!(g.profile().is_none());
+    let x = g.variable(2.0f32);
+    g.enable_profiling();
+    let y = g.variable(3.0f32);
+    let a = g.mul(&x, &y)?;
+    let b = g.mul(&a, &y)?;
+    let c = g.constant(1.0f32);
+    let d = g.sub(&b, &c)?;
+    let e = g.exp(&d);
+    // Constant operations are timed but create no node.
+    let _ = g.sin(&c);
+
+    let profile = g.profile().unwrap();
+    assert_eq!(profile.get("variable").unwrap().nodes, 1);
+    assert_eq!(profile.get("mul").unwrap().nodes, 2);
+    assert_eq!(profile.get("sub").unwrap().nodes, 1);
+    // `mul` retains both operands, `sub` only the ids of its inputs.
+    assert_eq!(profile.get("variable").unwrap().retained_bytes, 0);
+    assert_eq!(profile.get("mul").unwrap().retained_bytes, 16);
+    assert_eq!(profile.get("sub").unwrap().retained_bytes, 0);
+    assert_eq!(profile.get("exp").unwrap().retained_bytes, 4);
+    assert_eq!(profile.get("sin").unwrap().nodes, 0);
+    assert_eq!(profile.get("sin").unwrap().retained_bytes, 0);
+    assert_eq!(profile.total().nodes, 5);
+    assert_eq!(profile.total().backward_calls, 0);
+
+    g.evaluate_gradients(e.gid()?, 1.0)?;
+    // Copies of the graph share the statistics.
+    g.clone().evaluate_gradients_once(e.gid()?, 1.0)?;
+    let profile = g.profile().unwrap();
+    assert_eq!(profile.get("mul").unwrap().backward_calls, 4);
+    assert_eq!(profile.get("exp").unwrap().backward_calls, 2);
+    assert_eq!(profile.get("variable").unwrap().backward_calls, 0);
+
+    let report = profile.report();
+    assert!(report.starts_with("operator"));
+    assert!(report.lines().any(|line| line.starts_with("mul ")));
+    assert!(report.lines().last().unwrap().starts_with("total "));
+    Ok(())
+}
+
+#[test]
+fn test_anomaly_detection() -> Result<()> {
+    // Without detection, non-finite values go unnoticed.
+    let mut g = Graph1::new();
+    let x = g.variable(0.0f32);
+    let y = g.sqrt(&x);
+    let gradients = g.evaluate_gradients(y.gid()?, 1.0)?;
+    assert!(gradients.get(x.gid()?).unwrap().is_infinite());
+
+    // The gradient of `sqrt` is infinite at 0.
+    let mut g = Graph1::new();
+    g.enable_anomaly_detection();
+    let x = g.variable(0.0f32);
+    let y = g.sqrt(&x);
+    let z = g.mul(&y, &x)?;
+    g.check_anomalies()?;
+    match g.evaluate_gradients(z.gid()?, 1.0) {
+        Err(Error::Anomaly { op, node, kind, .. }) => {
+            assert_eq!(op, "sqrt");
+            assert_eq!(node, y.input());
+            assert_eq!(kind, AnomalyKind::Gradient);
+        }
+        _ => panic!("expected an anomaly"),
+    }
+
+    // The first non-finite forward value is reported.
+    let x = g.variable(-1.0f32);
+    let a = g.log(&x);
+    let b = g.exp(&a);
+    let c = g.constant(0.0f32);
+    let _ = g.div(&c, &c)?;
+    assert!(b.data().is_nan());
+    match g.check_anomalies() {
+        Err(Error::Anomaly { op, node, kind, .. }) => {
+            assert_eq!(op, "log");
+            assert_eq!(node, a.input());
+            assert_eq!(kind, AnomalyKind::Value);
+        }
+        _ => panic!("expected an anomaly"),
+    }
+    // Gradient computations only fail on the nodes that they reach.
+    assert!(matches!(
+        g.evaluate_gradients(b.gid()?, 1.0),
+        Err(Error::Anomaly { op, kind, .. }) if op == "exp" && kind == AnomalyKind::Value
+    ));
+    let w = g.variable(3.0f32);
+    let v = g.mul(&w, &w)?;
+    let gradients = g.evaluate_gradients(v.gid()?, 1.0)?;
+    assert!((gradients.get(w.gid()?).unwrap() - 6.0).abs() < 1e-6);
+    g.clear_anomalies();
+    g.check_anomalies()?;
+
+    // Operations on constants are checked too.
+    let mut g = GraphN::new();
+    g.enable_anomaly_detection();
+    let c = g.constant(0.0f32);
+    let _ = g.div(&c, &c)?;
+    assert!(matches!(
+        g.check_anomalies(),
+        Err(Error::Anomaly { node: None, .. })
+    ));
+
+    // Higher-order gradients are checked as graph values.
+    let mut g = GraphN::new();
+    g.enable_anomaly_detection();
+    let x = g.variable(0.0f32);
+    let y = g.sqrt(&x);
+    let one = g.constant(1.0f32);
+    assert!(matches!(
+        g.compute_gradients(y.gid()?, one),
+        Err(Error::Anomaly { op, .. }) if op == "sqrt"
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_gradient_hooks() -> Result<()> {
+    let mut g = Graph1::new();
+    let x = g.variable(2.0f32);
+    let c = g.constant(10.0f32);
+    let y = g.mul(&x, &c)?;
+    let z = g.mul(&y, &y)?;
+
+    // Clip the gradient of `y` then record the gradient of `x`.
+    g.register_hook(y.gid()?, |_, gradient: &mut f32| {
+        *gradient = gradient.min(1.0);
+        Ok(())
+    })?;
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    g.register_hook(x.gid()?, {
+        let seen = seen.clone();
+        move |_, gradient: &mut f32| {
+            seen.lock().unwrap().push(*gradient);
+            Ok(())
+        }
+    })?;
+    let gradients = g.evaluate_gradients(z.gid()?, 1.0)?;
+    assert_eq!(gradients.get(y.gid()?), Some(&1.0));
+    assert_eq!(gradients.get(x.gid()?), Some(&10.0));
+    let gradients = g.evaluate_gradients_once(z.gid()?, 0.015625)?;
+    assert_eq!(gradients.get(x.gid()?), Some(&6.25));
+    assert_eq!(*seen.lock().unwrap(), vec![10.0, 6.25]);
+
+    // Hooks may use the gradient algebra for higher-order gradients.
+    let mut g = GraphN::new();
+    let x = g.variable(3.0f32);
+    let y = g.mul(&x, &x)?;
+    g.register_hook(y.gid()?, |graph, gradient: &mut Value<f32>| {
+        *gradient = graph.mul(gradient, gradient)?;
+        Ok(())
+    })?;
+    let one = g.constant(2.0f32);
+    let gradients = g.compute_gradients(y.gid()?, one)?;
+    // dy/dx * 2^2
+    assert_eq!(gradients.get(x.gid()?).map(Value::data), Some(&24.0));
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_gradient_parallel() -> Result<()> {
+    let mut g = Graph1::new();
+
+    // An ensemble of branches sharing the same inputs.
+    let x = g.variable(0.5f64);
+    let y = g.variable(1.5f64);
+    let mut outputs = Vec::new();
+    for i in 0..16 {
+        let c = g.constant(i as f64);
+        let a = g.mul(&x, &c)?;
+        let b = g.mul(&a, &y)?;
+        let h = g.sin(&b);
+        let z = g.mul(&h, &x)?;
+        outputs.push(z);
+    }
+    let z = g.add_all(&outputs.iter().collect::<Vec<_>>())?;
+    let (x, y, z) = (x.gid()?, y.gid()?, z.gid()?);
+
+    let expected = g.evaluate_gradients(z, 1.0)?;
+    let store = g.evaluate_gradients_parallel(z, 1.0)?;
+    for id in &[x, y, z] {
+        assert!((store.get(*id).unwrap() - expected.get(*id).unwrap()).abs() < 1e-10);
+    }
+    let ids = outputs
+        .iter()
+        .map(|v| v.gid())
+        .collect::<Result<Vec<_>>>()?;
+    let seeds = ids.iter().map(|id| (*id, 2.0)).collect();
+    let store = g.evaluate_gradients_many_parallel(seeds)?;
+    assert!((store.get(x).unwrap() - 2.0 * expected.get(x).unwrap()).abs() < 1e-10);
+    assert!((store.get(y).unwrap() - 2.0 * expected.get(y).unwrap()).abs() < 1e-10);
+
+    // Hooks are also called in parallel mode.
+    for v in &outputs {
+        g.register_hook(v.gid()?, |_, gradient: &mut f64| {
+            *gradient = 0.0;
+            Ok(())
+        })?;
+    }
+    let store = g.evaluate_gradients_parallel(z, 1.0)?;
+    assert!(store.get(x).unwrap().abs() < 1e-10);
+    Ok(())
+}
+
 #[cfg(feature = "arrayfire")]
 mod af_graph_test {
     use super::*;
@@ -104,6 +690,25 @@ mod af_graph_test {
         Ok(())
     }
 
+    #[test]
+    fn test_anomaly_detection() -> Result<()> {
+        let dims = dim4!(2, 2);
+        let mut g = Graph1::new();
+        g.enable_anomaly_detection();
+        let a = g.variable(af::randu::<f32>(dims) + 0.5f32);
+        let b = g.log(&a);
+        g.check_anomalies()?;
+        let c = g.constant(af::constant(-2f32, dims));
+        let d = g.add(&a, &c)?;
+        let e = g.sqrt(&d);
+        assert!(matches!(
+            g.check_anomalies(),
+            Err(Error::Anomaly { node, .. }) if node == e.input()
+        ));
+        assert!(b.data().all_finite());
+        Ok(())
+    }
+
     #[test]
     fn test_hessian_and_more() -> Result<()> {
         let dims = dim4!(1);
@@ -151,4 +756,61 @@ mod af_graph_test {
         assert_eq!(dddz_dxdydy, Some(&2.0));
         Ok(())
     }
+
+    #[test]
+    fn test_jvp() -> Result<()> {
+        let mut g = GraphN::new();
+        let a = g.variable(af::randu::<f32>(dim4!(4, 3)));
+        let b = g.constant(af::randu::<f32>(dim4!(3, 5)));
+        let c = g.matmul_nn(&a, &b)?;
+
+        let direction = af::randu::<f32>(dim4!(4, 3));
+        let expected = af::matmul(&direction, b.data(), af::MatProp::NONE, af::MatProp::NONE);
+        let direction = g.constant(direction);
+        let products = g.jvp(&[a.gid()?], vec![direction], &[c])?;
+        testing::assert_almost_all_equal(products[0].as_ref().unwrap().data(), &expected, 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jvp_array_outputs() -> Result<()> {
+        let a = af::randu::<f32>(dim4!(4, 3));
+        let direction = af::randu::<f32>(dim4!(4, 3));
+
+        // Forward mode: tanh(a * a) along `direction`.
+        let mut f = Forward1::new();
+        let x = f.dual(a.clone(), direction.clone())?;
+        let y = {
+            let h = f.mul(&x, &x)?;
+            f.tanh(&h)
+        };
+        let expected = y.tangent().unwrap();
+
+        let mut g = GraphN::new();
+        let x = g.variable(a);
+        let y = {
+            let h = g.mul(&x, &x)?;
+            g.tanh(&h)
+        };
+        let direction = g.constant(direction);
+        let products = g.jvp(&[x.gid()?], vec![direction], &[y])?;
+        testing::assert_almost_all_equal(products[0].as_ref().unwrap().data(), expected, 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hessian_vector_product() -> Result<()> {
+        let mut g = GraphN::new();
+        let a = g.variable(af::randu::<f32>(dim4!(4, 3)));
+        // sum(a^3)
+        let b = g.mul(&a, &a)?;
+        let c = g.dot(&b, &a)?;
+
+        let v = af::randu::<f32>(dim4!(4, 3));
+        let expected = a.data() * &v * 6.0f32;
+        let v = g.constant(v);
+        let products = g.hessian_vector_product(c.gid()?, &[a.gid()?], vec![v])?;
+        testing::assert_almost_all_equal(products[0].as_ref().unwrap().data(), &expected, 0.001);
+        Ok(())
+    }
 }
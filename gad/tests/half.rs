@@ -0,0 +1,219 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "half")]
+// Reduced-precision values are compared exactly.
+#![allow(clippy::float_cmp)]
+#![allow(clippy::many_single_char_names)]
+
+use gad::{net::Using, prelude::*};
+
+#[inline]
+fn assert_near<T: Into<f32>>(x: T, y: f32, precision: f32) {
+    let x = x.into();
+    assert!(
+        (x - y).abs() <= precision * y.abs().max(1.0),
+        "{} != {}",
+        x,
+        y
+    );
+}
+
+fn get_value<A, D, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+where
+    A: CoreAlgebra<D, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V> + ConstArithAlgebra<V, i16>,
+{
+    // exp(x) * sin(y) + sqrt(y) / x^2
+    let a = g.exp(x);
+    let b = g.sin(y);
+    let a = g.mul(&a, &b)?;
+    let c = g.sqrt(y);
+    let d = g.powc(x, 2);
+    let c = g.div(&c, &d)?;
+    g.add(&a, &c)
+}
+
+/// The value and the gradients of `get_value` in `f32`.
+fn get_expected(x0: f32, y0: f32) -> Result<(f32, f32, f32)> {
+    let mut g = Graph1::new();
+    let x = g.variable(x0);
+    let y = g.variable(y0);
+    let z = get_value(&mut g, &x, &y)?;
+    let store = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+    Ok((
+        *z.data(),
+        *store.get(x.gid()?).unwrap(),
+        *store.get(y.gid()?).unwrap(),
+    ))
+}
+
+fn check_number<T>(precision: f32) -> Result<()>
+where
+    T: Number + From<f32> + Into<f32> + PartialOrd,
+    Eval: AnalyticAlgebra<T> + ConstArithAlgebra<T, i16>,
+{
+    let (x0, y0) = (0.7, 1.3);
+    let (z0, dx0, dy0) = get_expected(x0, y0)?;
+    let mut eval = Eval::default();
+    let z = get_value::<_, T, _>(&mut eval, &T::from(x0), &T::from(y0))?;
+    assert_near(z, z0, precision);
+    assert!(eval.max(&T::from(x0), &T::from(y0))? == T::from(y0));
+
+    let mut g = Graph1::new();
+    let x = g.variable(T::from(x0));
+    let y = g.variable(T::from(y0));
+    let z = get_value(&mut g, &x, &y)?;
+    assert_near(*z.data(), z0, precision);
+    let store = g.evaluate_gradients_once(z.gid()?, T::one())?;
+    assert_near(*store.get(x.gid()?).unwrap(), dx0, precision);
+    assert_near(*store.get(y.gid()?).unwrap(), dy0, precision);
+    Ok(())
+}
+
+#[test]
+fn test_half_numbers() -> Result<()> {
+    check_number::<F16>(0.01)?;
+    check_number::<Bf16>(0.05)?;
+
+    assert_eq!(format!("{}", F16::from(1.5)), "1.5");
+    assert!(!F16::from(1e6).is_finite_number());
+    assert!(Bf16::from(1e6).is_finite_number());
+    Ok(())
+}
+
+#[test]
+fn test_half_higher_order() -> Result<()> {
+    // z = x * x * x
+    let mut g = GraphN::new();
+    let x = g.variable(F16::from(2.0));
+    let z = g.mul(&x, &x)?;
+    let z = g.mul(&z, &x)?;
+    let one = g.constant(F16::from(1.0));
+    let dz = g.compute_gradients(z.gid()?, one.clone())?;
+    let dz_dx = dz.get(x.gid()?).unwrap().clone();
+    assert_eq!(*dz_dx.data(), F16::from(12.0));
+    let d2z = g.compute_gradients(dz_dx.gid()?, one)?;
+    assert_eq!(*d2z.get(x.gid()?).unwrap().data(), F16::from(12.0));
+    Ok(())
+}
+
+/// Full-precision weights of the network returned by `make_net`.
+type Master = Using<Using<((), ()), f32>, f32>;
+
+/// The squared error of `x -> a * x + b` on the input `(x, y)`.
+fn make_net<T>(
+    a: T,
+    b: T,
+) -> impl Net<Graph1, Input = (T, T), Output = Value<T>, Weights = impl WeightOps<T> + CastWeights<Master>>
+where
+    T: Number + CastWeights<f32>,
+{
+    let input = InputData::<T, Graph1>::new(()).and(InputData::new(()));
+    input
+        .using(WeightData::new(a))
+        .using(WeightData::new(b))
+        .map(|g, (((x, y), a), b)| {
+            let z = g.mul(&a, &x)?;
+            let z = g.add(&z, &b)?;
+            let z = g.sub(&z, &y)?;
+            g.mul(&z, &z)
+        })
+}
+
+#[test]
+fn test_mixed_precision() -> Result<()> {
+    let samples = (-2i16..=2)
+        .map(|x| (F16::from(x), F16::from(2 * x - 1)))
+        .collect::<Vec<_>>();
+    let mut train = make_net(F16::from(0.0), F16::from(0.0)).mixed_precision(1024.0);
+    let initial_loss = train.apply_gradient_step(0.0, samples.clone())?.unwrap();
+    let mut loss = initial_loss;
+    for _ in 0..200 {
+        loss = train.apply_gradient_step(-0.01, samples.clone())?.unwrap();
+    }
+    assert!(loss < initial_loss);
+    assert_eq!(train.loss_scale(), 1024.0);
+
+    // Master weights are kept in f32 and copied into the network.
+    let Using(Using(_, a), b) = train.master_weights().clone();
+    assert_near(a, 2.0, 0.01);
+    assert_near(b, -1.0, 0.01);
+    let Using(Using(_, a16), b16) = train.net().get_weights().cast_into();
+    assert_eq!(a16, f32::from(F16::from(a)));
+    assert_eq!(b16, f32::from(F16::from(b)));
+    Ok(())
+}
+
+#[test]
+fn test_mixed_precision_loss_scaling() -> Result<()> {
+    let samples = vec![(F16::from(2.0), F16::from(3.0))];
+    let mut train = make_net(F16::from(0.0), F16::from(0.0))
+        .mixed_precision(65536.0)
+        .with_growth_interval(2);
+    // The loss scale is finite in `F16`.
+    assert_eq!(train.loss_scale(), 32768.0);
+    // Gradients overflow (e.g. `12 * 32768` for `a`) until the loss scale is small enough.
+    let mut skipped = 0;
+    while train.apply_gradient_step(-0.01, samples.clone())?.is_none() {
+        skipped += 1;
+    }
+    assert_eq!(skipped, 3);
+    assert_eq!(train.loss_scale(), 4096.0);
+    let Using(Using(_, a), b) = train.master_weights().clone();
+    assert_near(a, 0.12, 0.001);
+    assert_near(b, 0.06, 0.001);
+
+    // The loss scale grows after two successful steps, then overflows again.
+    assert!(train.apply_gradient_step(-0.01, samples.clone())?.is_some());
+    assert_eq!(train.loss_scale(), 8192.0);
+    assert!(train.apply_gradient_step(-0.01, samples)?.is_none());
+    assert_eq!(train.loss_scale(), 4096.0);
+
+    // The loss scale stops growing at the largest power of two below the range of `F16`.
+    let samples = vec![(F16::from(0.0), F16::from(0.0))];
+    let mut train = make_net(F16::from(0.0), F16::from(0.0))
+        .mixed_precision(8192.0)
+        .with_growth_interval(1);
+    for _ in 0..4 {
+        assert!(train.apply_gradient_step(-0.01, samples.clone())?.is_some());
+    }
+    assert_eq!(train.loss_scale(), 32768.0);
+
+    // Gradients that overflow at the smallest loss scale are reported and the weights are
+    // left unchanged.
+    let mut train = make_net(F16::from(1.0), F16::from(0.0)).mixed_precision(8.0);
+    let samples = vec![(F16::from(200.0), F16::from(0.0))];
+    let mut skipped = 0;
+    let error = loop {
+        match train.apply_gradient_step(-0.01, samples.clone()) {
+            Ok(None) => skipped += 1,
+            Ok(Some(_)) => panic!("expected an overflow"),
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(skipped, 3);
+    assert!(matches!(
+        error,
+        Error::Anomaly {
+            kind: AnomalyKind::Gradient,
+            ..
+        }
+    ));
+    assert_eq!(train.loss_scale(), 1.0);
+    assert!(matches!(
+        train.apply_gradient_step(-0.01, samples),
+        Err(Error::Anomaly { .. })
+    ));
+    assert_eq!(train.loss_scale(), 1.0);
+    let Using(Using(_, a), b) = train.master_weights().clone();
+    assert_eq!((a, b), (1.0, 0.0));
+
+    // Overflows in the forward pass are reported.
+    let mut train = make_net(F16::from(300.0), F16::from(0.0)).mixed_precision(1.0);
+    let samples = vec![(F16::from(300.0), F16::from(0.0))];
+    assert!(matches!(
+        train.apply_gradient_step(-0.01, samples),
+        Err(Error::Anomaly { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![allow(clippy::many_single_char_names)]
+
+use gad::{jacobian::*, prelude::*};
+
+#[inline]
+fn assert_near(x: f32, y: f32) {
+    assert!((x - y).abs() < 0.001);
+}
+
+fn assert_all_near(x: &[Vec<f32>], y: &[Vec<f32>]) {
+    assert_eq!(x.len(), y.len());
+    for (u, v) in x.iter().zip(y) {
+        assert_eq!(u.len(), v.len());
+        for (a, b) in u.iter().zip(v) {
+            assert_near(*a, *b);
+        }
+    }
+}
+
+fn get_values<A, V>(g: &mut A, v: &[V]) -> Result<Vec<V>>
+where
+    A: CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V>,
+{
+    // [x * y, sin(x) + z, 2]
+    let a = g.mul(&v[0], &v[1])?;
+    let s = g.sin(&v[0]);
+    let b = g.add(&s, &v[2])?;
+    let c = g.constant(2.0);
+    Ok(vec![a, b, c])
+}
+
+#[test]
+fn test_jacobian() -> Result<()> {
+    let (x, y, z) = (0.7f32, 1.3f32, 0.2f32);
+    let expected = vec![
+        vec![y, x, 0.0],
+        vec![x.cos(), 0.0, 1.0],
+        vec![0.0, 0.0, 0.0],
+    ];
+    let j1 = jacobian(|g, v| get_values(g, v), &[x, y, z])?;
+    assert_all_near(&j1, &expected);
+    let j2 = jacobian_forward(|g, v| get_values(g, v), &[x, y, z])?;
+    assert_all_near(&j2, &expected);
+    Ok(())
+}
+
+#[test]
+fn test_jacobian_without_inputs() -> Result<()> {
+    let j1 = jacobian(|g, _| Ok(vec![g.constant(1.0f32); 3]), &[])?;
+    assert_eq!(j1, vec![Vec::<f32>::new(); 3]);
+    let j2 = jacobian_forward(|g, _| Ok(vec![g.constant(1.0f32)]), &[])?;
+    assert_eq!(j2, vec![Vec::<f32>::new()]);
+    Ok(())
+}
+
+#[test]
+fn test_hessian() -> Result<()> {
+    let (x, y) = (0.7f32, 1.3f32);
+    // x * y^2 + exp(x)
+    let h = hessian(
+        |g, v| {
+            let a = g.mul(&v[0], &v[1])?;
+            let b = g.mul(&a, &v[1])?;
+            let c = g.exp(&v[0]);
+            g.add(&b, &c)
+        },
+        &[x, y],
+    )?;
+    let expected = vec![vec![x.exp(), 2.0 * y], vec![2.0 * y, 2.0 * x]];
+    assert_all_near(&h, &expected);
+
+    // Linear functions have a zero Hessian.
+    let h = hessian(|g, v| g.add(&v[0], &v[1]), &[1i32, 2])?;
+    assert_eq!(h, vec![vec![0, 0], vec![0, 0]]);
+    Ok(())
+}
+
+#[test]
+fn test_hessian_records_gradient_once() -> Result<()> {
+    fn sum_of_products(g: &mut GraphN, v: &[Value<f32>]) -> Result<Value<f32>> {
+        let mut sum = g.mul(&v[0], &v[1])?;
+        for x in &v[2..] {
+            let y = g.mul(&sum, x)?;
+            sum = g.add(&sum, &y)?;
+        }
+        Ok(sum)
+    }
+    let inputs = [0.1f32, 0.2, 0.3, 0.4, 0.5];
+
+    // Nodes of the function and of its gradient.
+    let mut g = GraphN::new();
+    g.enable_profiling();
+    let v = inputs.iter().map(|x| g.variable(*x)).collect::<Vec<_>>();
+    let y = sum_of_products(&mut g, &v)?;
+    let one = g.constant(1.0f32);
+    g.compute_gradients(y.gid()?, one)?;
+    let expected = g.profile().unwrap().total().nodes;
+
+    // Copies of a profiled graph share the statistics.
+    let mut copy = None;
+    hessian(
+        |g, v| {
+            g.enable_profiling();
+            copy = Some(g.clone());
+            sum_of_products(g, v)
+        },
+        &inputs,
+    )?;
+    let nodes = copy.unwrap().profile().unwrap().total().nodes;
+    // The variables were created before profiling was enabled.
+    assert_eq!(nodes + inputs.len(), expected);
+    Ok(())
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_jacobian_test {
+    use super::*;
+    use arrayfire as af;
+
+    #[test]
+    fn test_array_jacobian() -> Result<()> {
+        let a = af::randu::<f32>(af::dim4!(4, 3));
+        let x = af::randu::<f32>(af::dim4!(3, 1));
+        let j = array_jacobian(
+            |g, x| {
+                let a = g.constant(a.clone());
+                g.matmul_nn(&a, x)
+            },
+            x,
+        )?;
+        testing::assert_almost_all_equal(&j, &a, 0.001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_hessian() -> Result<()> {
+        let x = af::randu::<f32>(af::dim4!(2, 3));
+        let h = array_hessian(
+            |g, x| {
+                // sum(x^3)
+                let y = g.mul(x, x)?;
+                g.dot(&y, x)
+            },
+            x.clone(),
+        )?;
+        let expected = af::diag_create(&af::flat(&(x * 6.0f32)), 0);
+        testing::assert_almost_all_equal(&h, &expected, 0.001);
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "macros")]
+// Divisions by constants are compared exactly.
+#![allow(clippy::float_cmp)]
+#![allow(clippy::many_single_char_names)]
+
+use gad::prelude::*;
+
+#[inline]
+fn assert_near(x: f32, y: f32) {
+    assert!((x - y).abs() < 0.001);
+}
+
+fn get_value<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+where
+    A: CoreAlgebra<f32, Value = V>
+        + ArithAlgebra<V>
+        + AnalyticAlgebra<V>
+        + ConstArithAlgebra<V, f32>,
+{
+    Ok(gad_expr!(
+        g,
+        x * sin(y) + exp(x / y) - 2.0f32 * (1.0f32 - x) / 4.0f32
+    ))
+}
+
+fn expected(x: f32, y: f32) -> f32 {
+    x * y.sin() + (x / y).exp() - 2.0 * (1.0 - x) / 4.0
+}
+
+#[test]
+fn test_gad_expr() -> Result<()> {
+    let (x0, y0) = (0.7f32, 1.3f32);
+    let z = get_value(&mut Eval::default(), &x0, &y0)?;
+    assert_near(z, expected(x0, y0));
+    get_value(&mut Check::default(), &(), &())?;
+
+    let mut g = Graph1::new();
+    let x = g.variable(x0);
+    let y = g.variable(y0);
+    let z = get_value(&mut g, &x, &y)?;
+    assert_near(*z.data(), expected(x0, y0));
+    let gradients = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+
+    let mut f = Forward1::new();
+    let x1 = f.dual(x0, 1.0)?;
+    let y1 = f.constant(y0);
+    let z1 = get_value(&mut f, &x1, &y1)?;
+    assert_near(*z1.tangent().unwrap(), *gradients.get(x.gid()?).unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_gad_expr_example() -> Result<()> {
+    fn f<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+    where
+        A: CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V>,
+    {
+        Ok(gad_expr!(g, x * sin(y) + exp(x / y)))
+    }
+    let (x0, y0) = (0.7f32, 1.3f32);
+    let z = f(&mut Eval::default(), &x0, &y0)?;
+    assert_near(z, x0 * y0.sin() + (x0 / y0).exp());
+    f(&mut Check::default(), &(), &())?;
+    let mut g = GraphN::new();
+    let x = g.variable(x0);
+    let y = g.variable(y0);
+    let z = f(&mut g, &x, &y)?;
+    assert_near(*z.data(), x0 * y0.sin() + (x0 / y0).exp());
+    Ok(())
+}
+
+#[test]
+fn test_gad_expr_higher_order() -> Result<()> {
+    let mut g = GraphN::new();
+    let x = g.variable(3.0f32);
+    // Nested calls, negation, and constant arguments.
+    let z = gad_expr!(g, -powc(x, 3u8) + x * x);
+    let one = g.constant(1.0);
+    let dz = g.compute_gradients(z.gid()?, one)?;
+    let dz_dx = dz.get(x.gid()?).unwrap();
+    assert_near(*dz_dx.data(), -27.0 + 6.0);
+    let one = g.constant(1.0);
+    let ddz = g.compute_gradients(dz_dx.gid()?, one)?;
+    assert_near(*ddz.get(x.gid()?).unwrap().data(), -18.0 + 2.0);
+    Ok(())
+}
+
+fn get_ratios<A, V>(g: &mut A, x: &V) -> Result<V>
+where
+    A: CoreAlgebra<f32, Value = V>
+        + ArithAlgebra<V>
+        + AnalyticAlgebra<V>
+        + ConstArithAlgebra<V, i16>,
+{
+    Ok(gad_expr!(g, x / 3 + 2 / x))
+}
+
+#[test]
+fn test_gad_expr_division() -> Result<()> {
+    let x0 = 0.7f32;
+    let z = get_ratios(&mut Eval::default(), &x0)?;
+    // Divisions by constants are exact.
+    assert_eq!(z, x0 / 3.0 + 2.0 / x0);
+
+    let mut g = Graph1::new();
+    let x = g.variable(x0);
+    let z = get_ratios(&mut g, &x)?;
+    let gradients = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+    assert_near(
+        *gradients.get(x.gid()?).unwrap(),
+        1.0 / 3.0 - 2.0 / (x0 * x0),
+    );
+    Ok(())
+}
+
+#[test]
+fn test_gad_expr_leaves() -> Result<()> {
+    let mut g = Graph1::new();
+    let v = vec![g.variable(2i32), g.variable(3i32)];
+    let x = gad_expr!(g, v[0]);
+    assert_eq!(x.gid()?, v[0].gid()?);
+    let c = gad_expr!(g, 1 + 2);
+    assert_eq!(*c.data(), 3);
+    assert!(c.id().is_none());
+    let z = gad_expr!(g, max(v[0], v[1]) * 2u8);
+    assert_eq!(*z.data(), 6);
+    Ok(())
+}
+
+#[test]
+fn test_gad_expr_concrete_algebra() {
+    fn f(g: &mut Graph1, x: &Value<f32>) -> Result<Value<f32>> {
+        Ok(gad_expr!(g, log(x) / x))
+    }
+    let mut g = Graph1::new();
+    let x = g.variable(1.0f32);
+    assert!(f(&mut g, &x).is_ok());
+}
+
+/// A user-defined operation that does not return a `Result`.
+trait Square<V> {
+    fn square(&mut self, v: &V) -> V;
+}
+
+impl Square<f32> for Eval {
+    fn square(&mut self, v: &f32) -> f32 {
+        v * v
+    }
+}
+
+#[test]
+fn test_gad_expr_user_operations() -> Result<()> {
+    fn f<A, V>(g: &mut A, x: &V) -> Result<V>
+    where
+        A: CoreAlgebra<f32, Value = V> + AnalyticAlgebra<V> + Square<V>,
+    {
+        Ok(gad_expr!(g, square(x) + sin(x), infallible = [square]))
+    }
+    let z = f(&mut Eval::default(), &3.0f32)?;
+    assert_near(z, 9.0 + 3.0f32.sin());
+    Ok(())
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_macros_test {
+    use super::*;
+    use arrayfire as af;
+
+    #[test]
+    fn test_dimension_error() {
+        fn f(g: &mut Graph1, x: &Value<af::Array<f32>>) -> Result<Value<af::Array<f32>>> {
+            Ok(gad_expr!(g, matmul_nn(x, x) + sigmoid(x)))
+        }
+        let mut g = Graph1::new();
+        let x = g.variable(af::randu::<f32>(af::dim4!(2, 3)));
+        assert!(matches!(f(&mut g, &x), Err(Error::Dimensions { .. })));
+    }
+}
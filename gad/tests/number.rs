@@ -0,0 +1,145 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// Fixed-point values are compared exactly.
+#![allow(clippy::float_cmp)]
+
+use gad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Rem, Sub};
+
+/// A user-defined fixed-point number with 16 fractional bits.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+struct Fixed(i64);
+
+const SHIFT: u32 = 16;
+
+impl Fixed {
+    fn from_f64(x: f64) -> Self {
+        Fixed((x * (1 << SHIFT) as f64).round() as i64)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / (1 << SHIFT) as f64
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Fixed(self.0 + other.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Fixed(self.0 - other.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Fixed((self.0 * other.0) >> SHIFT)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Fixed((self.0 << SHIFT) / other.0)
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        Fixed(self.0 % other.0)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Fixed(-self.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl num::Zero for Fixed {
+    fn zero() -> Self {
+        Fixed(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl num::One for Fixed {
+    fn one() -> Self {
+        Fixed(1 << SHIFT)
+    }
+}
+
+impl num::Num for Fixed {
+    type FromStrRadixErr = std::num::ParseIntError;
+
+    fn from_str_radix(s: &str, radix: u32) -> std::result::Result<Self, Self::FromStrRadixErr> {
+        Ok(Fixed(i64::from_str_radix(s, radix)? << SHIFT))
+    }
+}
+
+impl Number for Fixed {}
+
+#[test]
+fn test_user_number() -> Result<()> {
+    let x0 = Fixed::from_f64(1.5);
+    let y0 = Fixed::from_f64(-0.25);
+
+    let mut eval = Eval::default();
+    let z = eval.mul(&x0, &y0)?;
+    assert_eq!(z, Fixed::from_f64(-0.375));
+    eval.check().mul(&(), &())?;
+
+    // z = max(x * y + x, y)
+    let mut g = Graph1::new();
+    let x = g.variable(x0);
+    let y = g.variable(y0);
+    let z = g.mul(&x, &y)?;
+    let z = g.add(&z, &x)?;
+    let z = g.max(&z, &y)?;
+    assert_eq!(z.data().to_f64(), 1.125);
+    let store = g.evaluate_gradients(z.gid()?, Fixed::from_f64(1.0))?;
+    assert_eq!(store.get(x.gid()?).unwrap().to_f64(), 0.75);
+    assert_eq!(store.get(y.gid()?).unwrap().to_f64(), 1.5);
+    Ok(())
+}
+
+#[test]
+fn test_user_number_higher_order() -> Result<()> {
+    // z = x * x * x
+    let mut g = GraphN::new();
+    let x = g.variable(Fixed::from_f64(2.0));
+    let z = g.mul(&x, &x)?;
+    let z = g.mul(&z, &x)?;
+    let one = g.constant(Fixed::from_f64(1.0));
+    let dz = g.compute_gradients(z.gid()?, one.clone())?;
+    let dz_dx = dz.get(x.gid()?).unwrap().clone();
+    assert_eq!(dz_dx.data().to_f64(), 12.0);
+    let d2z = g.compute_gradients(dz_dx.gid()?, one)?;
+    assert_eq!(d2z.get(x.gid()?).unwrap().data().to_f64(), 12.0);
+    Ok(())
+}
@@ -106,3 +106,62 @@ fn test_symgraph1() -> Result<()> {
     assert_eq!(format!("{}", gradients.get(b.gid()?).unwrap()), "aa1");
     Ok(())
 }
+
+impl HasGradientReader for SymEval {
+    type GradientReader = gad::store::EmptyGradientMap;
+}
+
+/// Compute `x * x` for inputs of type `D`, without weights.
+#[derive(Clone)]
+struct SquareLayer<D>(std::marker::PhantomData<D>);
+
+impl<A, D, V> Net<A> for SquareLayer<D>
+where
+    A: HasGradientReader + CoreAlgebra<D, Value = V> + ArithAlgebra<V>,
+{
+    type Input = V;
+    type Output = V;
+    type Weights = ();
+    type GradientInfo = ();
+
+    fn eval_with_gradient_info(
+        &self,
+        g: &mut A,
+        input: Self::Input,
+    ) -> Result<(Self::Output, Self::GradientInfo)> {
+        Ok((g.mul(&input, &input)?, ()))
+    }
+
+    fn get_weights(&self) -> Self::Weights {}
+
+    fn update_weights(&mut self, _delta: Self::Weights) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_weights(&mut self, _weights: Self::Weights) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_weight_gradients(
+        &self,
+        _info: Self::GradientInfo,
+        _reader: &A::GradientReader,
+    ) -> Result<Self::Weights> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_symgraph1_checkpoint() -> Result<()> {
+    // The checkpointed network is evaluated with `SymEval`, then differentiated again on a
+    // temporary graph.
+    let layer = SquareLayer::<Exp<&str>>(std::marker::PhantomData);
+    let net = Net::<SymGraph1>::checkpoint(layer);
+    let mut g = SymGraph1::new();
+    let a = g.variable(Exp_::num("a"));
+    let b = net.eval(&mut g, a.clone())?;
+    assert_eq!(format!("{}", b.data()), "aa");
+    let gradients = g.evaluate_gradients_once(b.gid()?, Exp_::num("1"))?;
+    assert_eq!(format!("{}", gradients.get(a.gid()?).unwrap()), "(1a+a1)");
+    Ok(())
+}
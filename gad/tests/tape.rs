@@ -0,0 +1,98 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![allow(clippy::many_single_char_names)]
+
+use gad::{prelude::*, tape::Tape};
+
+#[inline]
+fn assert_near(x: f32, y: f32) {
+    assert!((x - y).abs() < 0.001);
+}
+
+#[test]
+fn test_operators() -> Result<()> {
+    let tape = Tape::new(Graph1::new());
+    let x = tape.variable(0.7f32);
+    let y = tape.variable(1.3f32);
+    let c = tape.constant(2.0f32);
+    // x * y - exp(x / y) + 2 * sin(-x)
+    let z = &x * &y - (&x / &y).exp() + c * (-&x).sin();
+    let (x0, y0) = (0.7f32, 1.3f32);
+    let expected = x0 * y0 - (x0 / y0).exp() + 2.0 * (-x0).sin();
+    assert_near(*z.value()?.data(), expected);
+
+    let (x, y, z) = (x.into_value()?, y.into_value()?, z.into_value()?);
+    let gradients = tape.into_inner().evaluate_gradients_once(z.gid()?, 1.0)?;
+    let dx = y0 - (x0 / y0).exp() / y0 - 2.0 * x0.cos();
+    let dy = x0 + (x0 / y0).exp() * x0 / (y0 * y0);
+    assert_near(*gradients.get(x.gid()?).unwrap(), dx);
+    assert_near(*gradients.get(y.gid()?).unwrap(), dy);
+    Ok(())
+}
+
+#[test]
+fn test_higher_order() -> Result<()> {
+    let tape = Tape::new(GraphN::new());
+    let x = tape.variable(3.0f32);
+    let z = (&x * &x * &x).into_value()?;
+    let x = x.into_value()?;
+
+    let mut g = tape.borrow_mut();
+    let one = g.constant(1.0);
+    let dz = g.compute_gradients(z.gid()?, one)?;
+    let dz_dx = dz.get(x.gid()?).unwrap();
+    assert_near(*dz_dx.data(), 27.0);
+
+    // Resume using the tape with the operator syntax.
+    drop(g);
+    let u = tape.wrap(dz_dx.clone()) * tape.constant(2.0);
+    let u = u.into_value()?;
+    let one = tape.borrow_mut().constant(1.0);
+    let ddz = tape.borrow_mut().compute_gradients(u.gid()?, one)?;
+    assert_near(*ddz.get(x.gid()?).unwrap().data(), 36.0);
+    Ok(())
+}
+
+#[test]
+fn test_other_algebras() -> Result<()> {
+    let tape = Tape::new(Eval::default());
+    let x = tape.variable(4.0f32);
+    assert_near((x.sqrt() + x.detach()).into_value()?, 6.0);
+
+    let tape = Tape::new(Forward1::new());
+    let x = tape.wrap(tape.borrow_mut().dual(2.0f32, 1.0)?);
+    let z = (&x * &x).pow(&tape.constant(0.5)).into_value()?;
+    assert_near(*z.data(), 2.0);
+    assert_near(*z.tangent().unwrap(), 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_different_tapes() {
+    let t1 = Tape::new(Graph1::new());
+    let t2 = Tape::new(Graph1::new());
+    let x = t1.variable(1i32);
+    let y = t2.variable(2i32);
+    assert!(matches!(
+        (&x + &y).value(),
+        Err(Error::ForeignNode { node: None, .. })
+    ));
+    // Errors propagate to later results.
+    assert!(((x + y) * t1.constant(2)).into_value().is_err());
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_tape_test {
+    use super::*;
+    use arrayfire as af;
+
+    #[test]
+    fn test_dimension_error() {
+        let tape = Tape::new(Graph1::new());
+        let x = tape.variable(af::randu::<f32>(af::dim4!(2, 3)));
+        let y = tape.variable(af::randu::<f32>(af::dim4!(3, 2)));
+        let z = (&x * &y).exp() + &x;
+        assert!(matches!(z.value(), Err(Error::Dimensions { .. })));
+    }
+}
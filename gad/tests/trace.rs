@@ -0,0 +1,312 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![allow(clippy::many_single_char_names)]
+
+use gad::{
+    prelude::*,
+    trace::{Replay, Trace},
+};
+
+#[inline]
+fn assert_near(x: f32, y: f32) {
+    assert!((x - y).abs() < 0.0001, "{} != {}", x, y);
+}
+
+fn get_value<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+where
+    A: CoreAlgebra<f32, Value = V> + ArithAlgebra<V> + AnalyticAlgebra<V>,
+    V: Clone,
+{
+    // x * sin(y) + exp(x / y) - tanh(x) * log1p(y) + sqrt(y) / (1 + sigmoid(x))
+    let a = g.sin(y);
+    let a = g.mul(x, &a)?;
+    let b = g.div(x, y)?;
+    let b = g.exp(&b);
+    let c = g.tanh(x);
+    let d = g.log1p(y);
+    let c = g.mul(&c, &d)?;
+    let e = g.sqrt(y);
+    let f = g.sigmoid(x);
+    let one = g.ones(&f);
+    let f = g.add(&one, &f)?;
+    let e = g.div(&e, &f)?;
+    let s = g.add(&a, &b)?;
+    let s = g.sub(&s, &c)?;
+    let s = g.add(&s, &e)?;
+    // cos(x)^y / x - log(y) * detach(x)
+    let p = g.cos(x);
+    let p = g.pow(&p, y)?;
+    let q = g.reciprocal(x);
+    let p = g.mul(&p, &q)?;
+    let l = g.log(y);
+    let z = g.detach(x);
+    let l = g.mul(&l, &z)?;
+    let l = g.neg(&l);
+    let p = g.add(&p, &l)?;
+    g.add(&s, &p)
+}
+
+fn get_other_value<A, V>(g: &mut A, x: &V, y: &V) -> Result<V>
+where
+    A: CoreAlgebra<f32, Value = V>
+        + ArithAlgebra<V>
+        + ConstArithAlgebra<V, i16>
+        + CompareAlgebra<V>,
+{
+    // relu(2 * x^3 + 1) * max(x, y) + abs(y)
+    let a = g.powc(x, 3);
+    let a = g.mulc(&a, 2);
+    let a = g.addc(&a, 1);
+    let a = g.relu(&a);
+    let b = g.max(x, y)?;
+    let a = g.mul(&a, &b)?;
+    let c = g.abs(y);
+    g.add(&a, &c)
+}
+
+#[test]
+fn test_trace() -> Result<()> {
+    let mut trace = Trace::<f32>::record(2, |g, v| get_value(g, &v[0], &v[1]))?;
+    assert_eq!(trace.num_inputs(), 2);
+    assert!(!trace.is_empty());
+
+    let mut eval = Eval::default();
+    for &(x0, y0) in &[(0.3f32, 1.7f32), (0.7, 1.3), (1.1, 0.5)] {
+        let expected = get_value(&mut eval, &x0, &y0)?;
+        assert_near(trace.eval(&mut eval, &[x0, y0])?, expected);
+        assert_near(trace.replay(&mut eval, &[x0, y0])?, expected);
+
+        let mut g = Graph1::new();
+        let x = g.variable(x0);
+        let y = g.variable(y0);
+        let z = get_value(&mut g, &x, &y)?;
+        let store = g.evaluate_gradients_once(z.gid()?, 2.0)?;
+
+        let (z1, gradients) = trace.eval_with_gradients(&mut eval, &[x0, y0], 2.0)?;
+        assert_near(z1, expected);
+        assert_near(gradients[0].unwrap(), *store.get(x.gid()?).unwrap());
+        assert_near(gradients[1].unwrap(), *store.get(y.gid()?).unwrap());
+
+        // Replaying on a graph gives the same gradients.
+        let mut g = Graph1::new();
+        let x2 = g.variable(x0);
+        let y2 = g.variable(y0);
+        let z2 = trace.replay(&mut g, &[x2.clone(), y2.clone()])?;
+        assert_near(*z2.data(), expected);
+        let store2 = g.evaluate_gradients_once(z2.gid()?, 2.0)?;
+        assert_near(
+            *store2.get(x2.gid()?).unwrap(),
+            *store.get(x.gid()?).unwrap(),
+        );
+        assert_near(
+            *store2.get(y2.gid()?).unwrap(),
+            *store.get(y.gid()?).unwrap(),
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_trace_operations() -> Result<()> {
+    let mut trace = Trace::<f32>::record(2, |g, v| get_other_value(g, &v[0], &v[1]))?;
+    let mut eval = Eval::default();
+    for &(x0, y0) in &[(0.3f32, 1.7f32), (-0.9, -1.3), (1.1, 0.5)] {
+        let expected = get_other_value(&mut eval, &x0, &y0)?;
+        assert_near(trace.replay(&mut eval, &[x0, y0])?, expected);
+
+        let mut g = Graph1::new();
+        let x = g.variable(x0);
+        let y = g.variable(y0);
+        let z = get_other_value(&mut g, &x, &y)?;
+        let store = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+
+        let (z1, gradients) = trace.eval_with_gradients(&mut eval, &[x0, y0], 1.0)?;
+        assert_near(z1, expected);
+        assert_near(gradients[0].unwrap(), *store.get(x.gid()?).unwrap());
+        assert_near(gradients[1].unwrap(), *store.get(y.gid()?).unwrap());
+
+        let mut g = Graph1::new();
+        let x2 = g.variable(x0);
+        let y2 = g.variable(y0);
+        let z2 = trace.replay(&mut g, &[x2.clone(), y2.clone()])?;
+        assert_near(*z2.data(), expected);
+        let store2 = g.evaluate_gradients_once(z2.gid()?, 1.0)?;
+        assert_near(
+            *store2.get(x2.gid()?).unwrap(),
+            *store.get(x.gid()?).unwrap(),
+        );
+        assert_near(
+            *store2.get(y2.gid()?).unwrap(),
+            *store.get(y.gid()?).unwrap(),
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_trace_without_gradients() -> Result<()> {
+    let mut trace = Trace::<f32>::record(3, |g, v| {
+        let c = g.constant(2.0);
+        let z = g.zeros(&v[2]);
+        let x = g.mul(&v[0], &c)?;
+        let y = g.detach(&v[1]);
+        let x = g.add(&x, &y)?;
+        g.add(&x, &z)
+    })?;
+    let mut eval = Eval::default();
+    let (z, gradients) = trace.eval_with_gradients(&mut eval, &[1.0, 3.0, 4.0], 1.0)?;
+    assert_near(z, 5.0);
+    assert_near(gradients[0].unwrap(), 2.0);
+    assert!(gradients[1].is_none());
+    assert!(gradients[2].is_none());
+
+    let mut g = Graph1::new();
+    let inputs = vec![g.variable(1.0), g.variable(3.0), g.variable(4.0)];
+    let z = trace.replay(&mut g, &inputs)?;
+    assert_near(*z.data(), 5.0);
+    let store = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+    assert_near(*store.get(inputs[0].gid()?).unwrap(), 2.0);
+    assert!(store.get(inputs[1].gid()?).is_none());
+    assert!(store.get(inputs[2].gid()?).is_none());
+
+    // The output is an input.
+    let mut trace = Trace::<f32>::record(2, |_, v| Ok(v[1]))?;
+    assert_near(trace.replay(&mut eval, &[1.0, 3.0])?, 3.0);
+    let (_, gradients) = trace.eval_with_gradients(&mut eval, &[1.0, 3.0], 1.0)?;
+    assert_eq!(gradients, vec![None, Some(1.0)]);
+    Ok(())
+}
+
+#[test]
+fn test_trace_creates_no_nodes() -> Result<()> {
+    let mut trace = Trace::<f32>::record(2, |g, v| get_value(g, &v[0], &v[1]))?;
+    let mut g = Graph1::new();
+    g.enable_profiling();
+    let x = g.variable(0.7f32);
+    let y = g.variable(1.3f32);
+    let mut eval = Eval::default();
+    for _ in 0..3 {
+        trace.eval_with_gradients(&mut eval, &[*x.data(), *y.data()], 1.0)?;
+    }
+    assert_eq!(g.profile().unwrap().total().nodes, 2);
+
+    // Replaying on a graph records every operation.
+    trace.replay(&mut g, &[x, y])?;
+    assert!(g.profile().unwrap().total().nodes > 2);
+    Ok(())
+}
+
+#[test]
+fn test_trace_errors() -> Result<()> {
+    let mut trace = Trace::<f32>::record(2, |g, v| g.mul(&v[0], &v[1]))?;
+    let mut eval = Eval::default();
+    assert!(matches!(
+        trace.replay(&mut eval, &[1.0]),
+        Err(Error::Lengths { .. })
+    ));
+    assert!(matches!(
+        trace.eval_with_gradients(&mut eval, &[1.0], 1.0),
+        Err(Error::Lengths { .. })
+    ));
+
+    // Values of another tracer are rejected.
+    let mut leaked = None;
+    Trace::<f32>::record(0, |g, _| {
+        let x = g.constant(1.0);
+        let x = g.exp(&x);
+        let x = g.exp(&x);
+        leaked = Some(x);
+        Ok(x)
+    })?;
+    let x = leaked.unwrap();
+    let other = Trace::<f32>::record(1, |g, v| g.add(&v[0], &x));
+    assert!(matches!(other, Err(Error::ForeignTrace { .. })));
+    let other = Trace::<f32>::record(1, |_, _| Ok(x));
+    assert!(matches!(other, Err(Error::ForeignTrace { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_traced_net() -> Result<()> {
+    // Square loss of the affine model `a * x + b`.
+    let mut trace = Trace::<f32>::record(4, |g, v| {
+        let y = g.mul(&v[0], &v[2])?;
+        let y = g.add(&y, &v[1])?;
+        let d = g.sub(&y, &v[3])?;
+        g.mul(&d, &d)
+    })?;
+    let samples = vec![vec![1.0, 3.0], vec![2.0, 5.0], vec![-1.0, -1.0]];
+
+    let mut net = vec![
+        WeightData::<f32, Graph1>::new(0.5),
+        WeightData::<f32, Graph1>::new(0.0),
+    ];
+    let loss = net.apply_traced_gradient_step(vec![(); 2], &mut trace, -0.01, samples.clone())?;
+    // Expected loss and gradients for (a, b) = (0.5, 0).
+    assert_near(loss, 2.5f32.powi(2) + 4.0f32.powi(2) + 0.5f32.powi(2));
+    let da = 2.0 * (-2.5 * 1.0 - 4.0 * 2.0 + 0.5 * -1.0);
+    let db = 2.0 * (-2.5 - 4.0 + 0.5);
+    assert_near(net[0].get_weights(), 0.5 - 0.01 * da);
+    assert_near(net[1].get_weights(), -0.01 * db);
+
+    let initial_loss = loss;
+    let mut loss = loss;
+    for _ in 0..100 {
+        loss = net.apply_traced_gradient_step(vec![(); 2], &mut trace, -0.01, samples.clone())?;
+    }
+    assert!(loss < initial_loss);
+    assert!(matches!(
+        net.apply_traced_gradient_step(vec![(); 2], &mut trace, -0.01, Vec::new()),
+        Err(Error::Empty { .. })
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "arrayfire")]
+mod af_trace_test {
+    use super::*;
+    use arrayfire as af;
+    use gad::trace::TracedScalar;
+
+    #[test]
+    fn test_array_trace() -> Result<()> {
+        // Square loss of a linear layer.
+        let trace = Trace::<af::Array<f32>, i16, TracedScalar>::record(3, |g, v| {
+            let a = g.matmul_nn(&v[0], &v[1])?;
+            let b = g.tanh(&a);
+            let d = g.sub(&b, &v[2])?;
+            let d = g.transpose(&d, false)?;
+            Ok(g.norm2(&d))
+        })?;
+        for _ in 0..3 {
+            let w0 = af::randu::<f32>(af::dim4!(2, 3));
+            let x0 = af::randu::<f32>(af::dim4!(3, 2));
+            let t0 = af::randu::<f32>(af::dim4!(2, 2));
+            let mut g = Graph1::new();
+            let w = g.variable(w0.clone());
+            let x = g.constant(x0.clone());
+            let t = g.constant(t0.clone());
+            let a = g.matmul_nn(&w, &x)?;
+            let b = g.tanh(&a);
+            let d = g.sub(&b, &t)?;
+            let d = g.transpose(&d, false)?;
+            let z = g.norm2(&d);
+            let store = g.evaluate_gradients_once(z.gid()?, 1.0)?;
+
+            let mut g2 = Graph1::new();
+            let w2 = g2.variable(w0);
+            let x2 = g2.constant(x0);
+            let t2 = g2.constant(t0);
+            let z2 = trace.replay(&mut g2, &[w2.clone(), x2, t2])?;
+            assert_near(*z2.data(), *z.data());
+            let store2 = g2.evaluate_gradients_once(z2.gid()?, 1.0)?;
+            testing::assert_almost_all_equal(
+                store2.get(w2.gid()?).unwrap(),
+                store.get(w.gid()?).unwrap(),
+                0.0001,
+            );
+        }
+        Ok(())
+    }
+}